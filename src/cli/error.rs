@@ -1,7 +1,15 @@
+use std::path::PathBuf;
+
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum CliError {
     #[error("module '{0}' not found")]
     ModuleNotFound(String),
+
+    #[error("'{0}' does not name a file or directory")]
+    InvalidPath(PathBuf),
+
+    #[error("configuration file '{0}' already exists")]
+    ConfigAlreadyExists(PathBuf),
 }
@@ -1,15 +1,24 @@
 mod error;
 mod modules;
+mod summary;
+
+use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 
+pub use error::CliError;
 use modules::Modules;
+pub use summary::{PARTIAL_FAILURE, ROLLBACK_FAILURE, Summary};
 
 #[derive(Debug, Parser)]
 #[command(version, about, long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
+
+    /// The profile to apply, defaults to the one listing the hostname
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -19,13 +28,129 @@ pub enum Command {
         /// The modules to load
         #[command(flatten)]
         modules: Modules,
+
+        /// Print the operations without changing anything
+        #[arg(short('n'), long)]
+        dry_run: bool,
+
+        /// Take over files already at the destination instead of failing
+        #[arg(long, conflicts_with = "backup")]
+        adopt: bool,
+
+        /// Move files already at the destination to `<dst>.pkgs-bak`, restored on unload
+        #[arg(long)]
+        backup: bool,
+
+        /// Replace or remove symlinks even if something else re-pointed them
+        #[arg(long)]
+        force: bool,
+
+        /// Roll back every module loaded in this run if any of them fails
+        #[arg(long)]
+        atomic: bool,
+
+        /// Stop at the first module that fails
+        #[arg(long)]
+        fail_fast: bool,
     },
 
-    /// Unload modules
+    /// Unload modules, including those removed from the configuration file
     Unload {
         /// The modules to unload
         #[command(flatten)]
         modules: Modules,
+
+        /// Print the operations without changing anything
+        #[arg(short('n'), long)]
+        dry_run: bool,
+
+        /// Remove symlinks even if something else re-pointed them
+        #[arg(long)]
+        force: bool,
+
+        /// Fail on symlinks already removed instead of skipping them with a warning
+        #[arg(long)]
+        strict: bool,
+
+        /// Stop at the first module that fails
+        #[arg(long)]
+        fail_fast: bool,
+    },
+
+    /// Unload modules that are loaded but no longer in the configuration file
+    Prune {
+        /// Print the operations without changing anything
+        #[arg(short('n'), long)]
+        dry_run: bool,
+
+        /// Remove symlinks even if something else re-pointed them
+        #[arg(long)]
+        force: bool,
+
+        /// Fail on symlinks already removed instead of skipping them with a warning
+        #[arg(long)]
+        strict: bool,
+
+        /// Stop at the first module that fails
+        #[arg(long)]
+        fail_fast: bool,
+    },
+
+    /// Fix symlinks of loaded modules which point anywhere other than their source
+    Relink {
+        /// The modules to relink
+        #[command(flatten)]
+        modules: Modules,
+
+        /// Print the operations without changing anything
+        #[arg(short('n'), long)]
+        dry_run: bool,
+    },
+
+    /// Fetch git modules to their latest revision and reload them
+    Update {
+        /// The modules to update
+        #[command(flatten)]
+        modules: Modules,
+
+        /// Stop at the first module that fails
+        #[arg(long)]
+        fail_fast: bool,
+    },
+
+    /// Create a configuration file with every subdirectory as a module
+    Init {
+        /// Write `pkgs.yaml` instead of `pkgs.toml`
+        #[arg(long)]
+        yaml: bool,
+
+        /// Propose maps for files with the same name under `$HOME/.config`
+        #[arg(long)]
+        from_home: bool,
+    },
+
+    /// Move a file or directory into a module, map it in the configuration file and link it back
+    Add {
+        /// The module to add to
+        module: String,
+
+        /// The file or directory to add
+        path: PathBuf,
+    },
+
+    /// Undo an operation that was interrupted before it finished
+    Recover,
+
+    /// Show the state of every module, exit with non-zero status if any needs attention
+    Status,
+
+    /// Report every problem of the configuration file without changing anything, exit with
+    /// non-zero status if any is found
+    #[command(alias = "validate")]
+    Check {
+        /// Print the problems as a json array
+        #[arg(long)]
+        json: bool,
     },
 
     /// List available modules
@@ -0,0 +1,159 @@
+use std::fmt;
+use std::process::ExitCode;
+
+/// Exit status when some modules failed and were rolled back.
+pub const PARTIAL_FAILURE: u8 = 2;
+
+/// Exit status when rolling back a failed module failed too.
+pub const ROLLBACK_FAILURE: u8 = 3;
+
+/// Outcome of every module handled in one run of `load`, `unload` or `update`.
+#[derive(Debug)]
+pub struct Summary {
+    action: &'static str,
+    pub done: Vec<String>,
+    pub failed: Vec<String>,
+    /// Modules which succeeded but were rolled back because another one failed
+    pub rolled_back: Vec<String>,
+    pub rollback_failed: Option<String>,
+    /// Nothing was changed, the modules only show what would have been done
+    dry_run: bool,
+}
+
+impl Summary {
+    /// `action` is the past tense shown for the modules which succeeded, e.g. "Loaded", or what
+    /// would be done to them in a dry run, e.g. "Would load".
+    pub fn new(action: &'static str) -> Self {
+        Self {
+            action,
+            done: vec![],
+            failed: vec![],
+            rolled_back: vec![],
+            rollback_failed: None,
+            dry_run: false,
+        }
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.done.is_empty() && self.failed.is_empty() && self.rolled_back.is_empty()
+    }
+
+    pub fn exit_code(&self) -> ExitCode {
+        if self.rollback_failed.is_some() {
+            ExitCode::from(ROLLBACK_FAILURE)
+        } else if !self.failed.is_empty() {
+            ExitCode::from(PARTIAL_FAILURE)
+        } else {
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = [
+            (self.action, &self.done),
+            ("Failed", &self.failed),
+            ("Rolled back", &self.rolled_back),
+        ];
+
+        if self.dry_run {
+            writeln!(f, "Summary (dry run):")?;
+        } else {
+            writeln!(f, "Summary:")?;
+        }
+        for (label, modules) in lines {
+            if !modules.is_empty() {
+                writeln!(f, "  {label}: {}", modules.join(", "))?;
+            }
+        }
+        if let Some(module) = &self.rollback_failed {
+            writeln!(
+                f,
+                "  Rollback of {module} failed, run `pkgs recover` to undo the interrupted operation."
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::test_utils::prelude::*;
+
+    #[gtest]
+    fn success() {
+        let mut summary = Summary::new("Loaded");
+        summary.done = vec!["a".into(), "b".into()];
+
+        expect_eq!(summary.exit_code(), ExitCode::SUCCESS);
+        expect_eq!(summary.to_string(), "Summary:\n  Loaded: a, b\n");
+    }
+
+    #[gtest]
+    fn dry_run() {
+        let mut summary = Summary::new("Would load").with_dry_run(true);
+        summary.done = vec!["a".into()];
+
+        expect_eq!(summary.to_string(), "Summary (dry run):\n  Would load: a\n");
+    }
+
+    #[gtest]
+    fn partial_failure() {
+        let mut summary = Summary::new("Unloaded");
+        summary.done = vec!["a".into()];
+        summary.failed = vec!["b".into()];
+
+        expect_eq!(summary.exit_code(), ExitCode::from(PARTIAL_FAILURE));
+        expect_eq!(
+            summary.to_string(),
+            indoc! {"
+                Summary:
+                  Unloaded: a
+                  Failed: b
+            "}
+        );
+    }
+
+    #[gtest]
+    fn atomic() {
+        let mut summary = Summary::new("Loaded");
+        summary.failed = vec!["c".into()];
+        summary.rolled_back = vec!["a".into(), "b".into()];
+
+        expect_eq!(summary.exit_code(), ExitCode::from(PARTIAL_FAILURE));
+        expect_eq!(
+            summary.to_string(),
+            indoc! {"
+                Summary:
+                  Failed: c
+                  Rolled back: a, b
+            "}
+        );
+    }
+
+    #[gtest]
+    fn rollback_failure() {
+        let mut summary = Summary::new("Loaded");
+        summary.failed = vec!["b".into()];
+        summary.rollback_failed = Some("b".into());
+
+        expect_eq!(summary.exit_code(), ExitCode::from(ROLLBACK_FAILURE));
+        expect_eq!(
+            summary.to_string(),
+            indoc! {"
+                Summary:
+                  Failed: b
+                  Rollback of b failed, run `pkgs recover` to undo the interrupted operation.
+            "}
+        );
+    }
+}
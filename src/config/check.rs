@@ -0,0 +1,245 @@
+use std::fmt::{self, Display, Formatter};
+
+use serde::Serialize;
+
+use super::{Condition, Config, NamedPackage, PkgsParseError, VarMap, find_dst_conflicts};
+
+/// Kind of a [`Problem`], stable for machine-readable output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProblemKind {
+    /// The configuration file could not be found or parsed
+    InvalidConfig,
+    /// The selected profile does not exist or does not apply
+    InvalidProfile,
+    /// A variable could not be resolved
+    InvalidVar,
+    /// A map ending with a slash whose source has no file name
+    NoneFilename,
+    /// The git fields of a package are missing or conflict
+    InvalidGit,
+    /// The directory of a local package does not exist
+    PkgDirNotFound,
+    /// The source of a map does not exist in the package directory
+    SrcNotExists,
+    /// A destination is mapped twice, or inside the destination of another map
+    DstConflict,
+}
+
+/// A problem found in the configuration file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Problem {
+    pub kind: ProblemKind,
+    /// The package the problem was found in, absent for the top level of the configuration
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    pub message: String,
+}
+
+impl Problem {
+    pub fn new(kind: ProblemKind, package: Option<&str>, message: impl ToString) -> Self {
+        Self {
+            kind,
+            package: package.map(str::to_string),
+            message: message.to_string(),
+        }
+    }
+
+    fn parse(package: Option<&str>, err: PkgsParseError) -> Self {
+        let kind = match &err {
+            PkgsParseError::VarsBuild(_) | PkgsParseError::VarsParse(_) => ProblemKind::InvalidVar,
+            PkgsParseError::NoneFilename(_) => ProblemKind::NoneFilename,
+            PkgsParseError::GitUrlMissing
+            | PkgsParseError::GitFieldOnLocal(_)
+            | PkgsParseError::GitRevisionConflict => ProblemKind::InvalidGit,
+        };
+        Self::new(kind, package, err)
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match &self.package {
+            Some(package) => write!(f, "{package}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Config {
+    /// Resolve every package like [`Config::validate`], but collect every problem instead of
+    /// stopping at the first one. Returns the packages resolved without problems.
+    pub fn check(&self) -> (Vec<NamedPackage>, Vec<Problem>) {
+        let vars = match VarMap::try_new(&self.vars) {
+            Ok(vars) => vars,
+            Err(err) => return (vec![], vec![Problem::parse(None, err.into())]),
+        };
+
+        let mut packages = vec![];
+        let mut problems = vec![];
+        for name in self.packages.keys() {
+            match self.get(name) {
+                Ok(package) if !package.is_active() => {}
+                Ok(package) => packages.push(package),
+                Err(err) => {
+                    let mut errors = self.package_errors(name, &vars);
+                    if errors.is_empty() {
+                        errors.push(err);
+                    }
+                    problems.extend(errors.into_iter().map(|e| Problem::parse(Some(name), e)));
+                }
+            }
+        }
+
+        for conflict in find_dst_conflicts(&packages) {
+            problems.push(Problem::new(
+                ProblemKind::DstConflict,
+                Some(conflict.package()),
+                &conflict,
+            ));
+        }
+
+        (packages, problems)
+    }
+
+    /// Every error of the package `name`, where [`Config::get`] only returns the first one. Maps
+    /// skipped by their condition are left out.
+    fn package_errors(&self, name: &str, vars: &VarMap) -> Vec<PkgsParseError> {
+        let package = &self.packages[name];
+
+        let mut vars = self.with_package_vars(name, vars.clone());
+        if let Err(err) = vars.extends(&package.vars) {
+            return vec![err.into()];
+        }
+
+        let mut errors: Vec<_> = package
+            .maps
+            .iter()
+            .filter(|(_, dst)| dst.when().is_none_or(Condition::holds))
+            .filter_map(|(src, dst)| NamedPackage::parse_map(&vars, src, dst.dst()).err())
+            .collect();
+        if let Err(err) = NamedPackage::parse_git_source(package, &vars) {
+            errors.push(err);
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::config::Package;
+    use crate::test_utils::prelude::*;
+
+    fn config(packages: &[(&str, &[(&str, &str)])]) -> Config {
+        let packages = packages
+            .iter()
+            .map(|(name, maps)| {
+                let package = Package {
+                    maps: maps
+                        .iter()
+                        .map(|(src, dst)| (src.to_string(), (*dst).into()))
+                        .collect(),
+                    ..Default::default()
+                };
+                (name.to_string(), package)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        Config {
+            packages,
+            ..Default::default()
+        }
+    }
+
+    fn kinds(problems: &[Problem]) -> Vec<(ProblemKind, Option<&str>)> {
+        problems
+            .iter()
+            .map(|p| (p.kind, p.package.as_deref()))
+            .collect()
+    }
+
+    #[gtest]
+    fn no_problem() {
+        let (packages, problems) = config(&[("a", &[("file", "/dst/a")])]).check();
+
+        expect_eq!(packages.len(), 1);
+        expect_that!(problems, is_empty());
+    }
+
+    #[gtest]
+    fn report_every_problem() {
+        let config = config(&[
+            ("a", &[("file", "${UNKNOWN}"), ("dir", "${OTHER")]),
+            ("b", &[("..", "/dst/")]),
+            ("c", &[("file", "/dst/c")]),
+            ("d", &[("file", "/dst/c")]),
+        ]);
+
+        let (packages, problems) = config.check();
+        expect_eq!(packages.len(), 2);
+        expect_eq!(
+            kinds(&problems),
+            [
+                (ProblemKind::InvalidVar, Some("a")),
+                (ProblemKind::InvalidVar, Some("a")),
+                (ProblemKind::NoneFilename, Some("b")),
+                (ProblemKind::DstConflict, Some("c")),
+            ]
+        );
+        expect_eq!(
+            problems[3].to_string(),
+            "c: '/dst/c' is mapped by both 'c' and 'd'"
+        );
+    }
+
+    #[gtest]
+    fn duplicate_dst_in_same_package() {
+        let config = config(&[("a", &[("f1", "/dst/same"), ("f2", "/dst/same")])]);
+
+        let (_, problems) = config.check();
+        expect_eq!(kinds(&problems), [(ProblemKind::DstConflict, Some("a"))]);
+        expect_eq!(
+            problems[0].to_string(),
+            "a: '/dst/same' is mapped by both 'f1' and 'f2' of 'a'"
+        );
+    }
+
+    #[gtest]
+    fn invalid_global_var() {
+        let mut config = config(&[("a", &[("file", "/dst/a")])]);
+        config.vars = vec![("VAR".into(), "${UNKNOWN}".into())];
+
+        let (packages, problems) = config.check();
+        expect_that!(packages, is_empty());
+        expect_eq!(kinds(&problems), [(ProblemKind::InvalidVar, None)]);
+    }
+
+    #[gtest]
+    fn invalid_git() {
+        let mut config = config(&[("a", &[("file", "/dst/a")])]);
+        config.packages.get_mut("a").unwrap().url = Some("url".into());
+
+        let (_, problems) = config.check();
+        expect_eq!(kinds(&problems), [(ProblemKind::InvalidGit, Some("a"))]);
+    }
+
+    #[gtest]
+    fn serialize() -> Result<()> {
+        let problem = Problem::new(ProblemKind::SrcNotExists, Some("a"), "missing");
+        expect_eq!(
+            serde_json::to_string(&problem)?,
+            r#"{"kind":"src-not-exists","package":"a","message":"missing"}"#
+        );
+
+        let problem = Problem::new(ProblemKind::InvalidConfig, None, "bad");
+        expect_eq!(
+            serde_json::to_string(&problem)?,
+            r#"{"kind":"invalid-config","message":"bad"}"#
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,210 @@
+use std::env;
+use std::path::Path;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::fs::host_name;
+
+/// Condition under which a package or a map is used. Every field given must hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Condition {
+    /// Operating systems, one of which must be the current one, e.g. `linux`, `macos` or `windows`
+    #[serde(default)]
+    pub os: Vec<String>,
+
+    /// Architectures, one of which must be the current one, e.g. `x86_64` or `aarch64`
+    #[serde(default)]
+    pub arch: Vec<String>,
+
+    /// Hostname patterns, one of which must match, where `*` matches any characters
+    #[serde(default)]
+    pub host: Vec<String>,
+
+    /// Environment variables which must all be set
+    #[serde(default)]
+    pub env: Vec<String>,
+
+    /// Executables which must all be found on `PATH`
+    #[serde(default)]
+    pub command: Vec<String>,
+}
+
+/// Destination of a map, either a plain path or one used only under a condition.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum MapTarget {
+    Path(String),
+    Conditional(ConditionalTarget),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ConditionalTarget {
+    pub dst: String,
+    pub when: Condition,
+}
+
+impl Condition {
+    pub fn holds(&self) -> bool {
+        let any_of = |values: &[String], current: &str| {
+            values.is_empty() || values.iter().any(|v| v == current)
+        };
+
+        any_of(&self.os, env::consts::OS)
+            && any_of(&self.arch, env::consts::ARCH)
+            && (self.host.is_empty()
+                || host_name().is_some_and(|host| {
+                    self.host
+                        .iter()
+                        .any(|pattern| matches_pattern(pattern, &host))
+                }))
+            && self.env.iter().all(|var| env::var_os(var).is_some())
+            && self.command.iter().all(|cmd| find_executable(cmd))
+    }
+}
+
+impl MapTarget {
+    pub fn dst(&self) -> &str {
+        match self {
+            MapTarget::Path(dst) | MapTarget::Conditional(ConditionalTarget { dst, .. }) => dst,
+        }
+    }
+
+    pub fn when(&self) -> Option<&Condition> {
+        match self {
+            MapTarget::Path(_) => None,
+            MapTarget::Conditional(target) => Some(&target.when),
+        }
+    }
+}
+
+impl From<&str> for MapTarget {
+    fn from(dst: &str) -> Self {
+        MapTarget::Path(dst.to_string())
+    }
+}
+
+impl From<String> for MapTarget {
+    fn from(dst: String) -> Self {
+        MapTarget::Path(dst)
+    }
+}
+
+/// Match `text` against `pattern`, where `*` matches any sequence of characters.
+pub(super) fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let Some((first, rest)) = pattern.split_once('*') else {
+        return pattern == text;
+    };
+    let Some(mut text) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts: Vec<_> = rest.split('*').collect();
+    let last = parts.pop().unwrap_or_default();
+    for part in parts {
+        match text.find(part) {
+            Some(i) => text = &text[i + part.len()..],
+            None => return false,
+        }
+    }
+    text.len() >= last.len() && text.ends_with(last)
+}
+
+fn find_executable(name: &str) -> bool {
+    if name.contains(std::path::MAIN_SEPARATOR) {
+        return Path::new(name).is_file();
+    }
+
+    let Some(paths) = env::var_os("PATH") else {
+        return false;
+    };
+    env::split_paths(&paths).any(|dir| {
+        let path = dir.join(name);
+        path.is_file() || (cfg!(windows) && path.with_extension("exe").is_file())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::prelude::*;
+
+    #[gtest]
+    fn empty_holds() {
+        expect_true!(Condition::default().holds());
+    }
+
+    #[gtest]
+    fn os_and_arch() {
+        let condition = Condition {
+            os: vec!["other".into(), env::consts::OS.into()],
+            arch: vec![env::consts::ARCH.into()],
+            ..Default::default()
+        };
+        expect_true!(condition.holds());
+
+        let condition = Condition {
+            os: vec!["other".into()],
+            ..Default::default()
+        };
+        expect_false!(condition.holds());
+    }
+
+    #[gtest]
+    fn env_and_command() {
+        let condition = Condition {
+            env: vec!["PATH".into()],
+            command: vec!["sh".into()],
+            ..Default::default()
+        };
+        expect_true!(condition.holds());
+
+        let condition = Condition {
+            env: vec!["PATH".into(), "PKGS_SURELY_UNSET_VAR".into()],
+            ..Default::default()
+        };
+        expect_false!(condition.holds());
+
+        let condition = Condition {
+            command: vec!["pkgs-surely-missing-command".into()],
+            ..Default::default()
+        };
+        expect_false!(condition.holds());
+    }
+
+    #[gtest]
+    fn pattern() {
+        expect_true!(matches_pattern("laptop", "laptop"));
+        expect_false!(matches_pattern("laptop", "laptop-2"));
+        expect_true!(matches_pattern("*", "anything"));
+        expect_true!(matches_pattern("work-*", "work-laptop"));
+        expect_true!(matches_pattern("*-laptop", "work-laptop"));
+        expect_true!(matches_pattern("w*k*p", "work-laptop"));
+        expect_false!(matches_pattern("a*a", "a"));
+        expect_false!(matches_pattern("server-*", "work-laptop"));
+    }
+
+    #[gtest]
+    fn deserialize_map_target() -> Result<()> {
+        let targets: Vec<MapTarget> =
+            serde_json::from_str(r#"["dst", { "dst": "other", "when": { "os": ["linux"] } }]"#)?;
+
+        expect_eq!(
+            targets,
+            [
+                MapTarget::from("dst"),
+                MapTarget::Conditional(ConditionalTarget {
+                    dst: "other".into(),
+                    when: Condition {
+                        os: vec!["linux".into()],
+                        ..Default::default()
+                    },
+                }),
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,591 @@
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+use toml_edit::{DocumentMut, InlineTable, Item, Table, TomlError};
+
+use super::Config;
+
+#[derive(Debug, Error)]
+pub enum ConfigEditError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error("TOML parse error: {0}")]
+    TomlParse(#[from] TomlError),
+
+    #[error("unsupported file format: {0}")]
+    UnsupportedFileFormat(PathBuf),
+
+    #[error("package '{0}' not found in configuration file")]
+    PackageNotFound(String),
+
+    #[error("'{src}' is already mapped in package '{package}'")]
+    MapAlreadyExists { package: String, src: String },
+
+    #[error("could not edit '{0}' in configuration file, only block style is supported")]
+    UnsupportedLayout(String),
+}
+
+impl Config {
+    /// Return the content of the configuration file at `path` with `src = dst` appended to the
+    /// maps of `package`. Comments and ordering are kept; the file itself is left untouched.
+    pub fn add_map(
+        path: &Path,
+        package: &str,
+        src: &str,
+        dst: &str,
+    ) -> Result<String, ConfigEditError> {
+        let content = fs::read_to_string(path)?;
+
+        match path.extension().and_then(|s| s.to_str()) {
+            Some("toml") => add_map_to_toml(&content, package, src, dst),
+            Some("yaml") | Some("yml") => add_map_to_yaml(&content, package, src, dst),
+            _ => Err(ConfigEditError::UnsupportedFileFormat(path.to_path_buf())),
+        }
+    }
+}
+
+fn add_map_to_toml(
+    content: &str,
+    package: &str,
+    src: &str,
+    dst: &str,
+) -> Result<String, ConfigEditError> {
+    let mut doc: DocumentMut = content.parse()?;
+
+    let pkg = doc
+        .get_mut("packages")
+        .and_then(|pkgs| pkgs.get_mut(package))
+        .filter(|pkg| !pkg.is_none())
+        .ok_or_else(|| ConfigEditError::PackageNotFound(package.to_string()))?;
+    let inline = pkg.is_inline_table();
+    let pkg = pkg
+        .as_table_like_mut()
+        .ok_or_else(|| ConfigEditError::UnsupportedLayout(format!("packages.{package}")))?;
+
+    if pkg.get("maps").is_none() {
+        let maps = if inline {
+            Item::Value(InlineTable::new().into())
+        } else {
+            Item::Table(Table::new())
+        };
+        pkg.insert("maps", maps);
+    }
+
+    let maps = pkg
+        .get_mut("maps")
+        .and_then(Item::as_table_like_mut)
+        .ok_or_else(|| ConfigEditError::UnsupportedLayout(format!("packages.{package}.maps")))?;
+
+    if maps.contains_key(src) {
+        return Err(ConfigEditError::MapAlreadyExists {
+            package: package.to_string(),
+            src: src.to_string(),
+        });
+    }
+    maps.insert(src, toml_edit::value(dst));
+
+    Ok(doc.to_string())
+}
+
+/// A line of a YAML document, with its indentation and the key it defines if any.
+struct YamlLine<'a> {
+    indent: usize,
+    blank: bool,
+    comment: bool,
+    key: Option<String>,
+    rest: &'a str,
+}
+
+impl<'a> YamlLine<'a> {
+    fn parse(line: &'a str) -> Self {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let trimmed = trimmed.trim_end();
+
+        let blank = trimmed.is_empty();
+        let comment = trimmed.starts_with('#');
+        let (key, rest) = match Self::split_key(trimmed) {
+            Some((key, rest)) if !blank && !comment => (Some(key), rest),
+            _ => (None, ""),
+        };
+
+        Self {
+            indent,
+            blank,
+            comment,
+            key,
+            rest,
+        }
+    }
+
+    fn split_key(line: &str) -> Option<(String, &str)> {
+        let (key, rest) = match line.chars().next()? {
+            quote @ ('"' | '\'') => {
+                let end = line[1..].find(quote)? + 1;
+                (line[1..end].to_string(), &line[end + 1..])
+            }
+            _ => {
+                let end = match line.find(": ") {
+                    Some(end) => end,
+                    None => line.strip_suffix(':')?.len(),
+                };
+                (line[..end].trim_end().to_string(), &line[end..])
+            }
+        };
+        let rest = rest.trim_start().strip_prefix(':')?.trim();
+        Some((key, rest))
+    }
+
+    fn is_content(&self) -> bool {
+        !self.blank && !self.comment
+    }
+
+    /// Whether the key has no inline value, so its children are on the following lines.
+    fn is_block(&self) -> bool {
+        self.rest.is_empty() || self.rest.starts_with('#')
+    }
+
+    /// The trailing comment if the value is an empty flow mapping `{}`.
+    fn empty_flow_map(&self) -> Option<&str> {
+        let comment = self.rest.strip_prefix("{}")?.trim_start();
+        (comment.is_empty() || comment.starts_with('#')).then_some(comment)
+    }
+}
+
+fn add_map_to_yaml(
+    content: &str,
+    package: &str,
+    src: &str,
+    dst: &str,
+) -> Result<String, ConfigEditError> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let parsed: Vec<YamlLine> = lines.iter().map(|l| YamlLine::parse(l)).collect();
+
+    let not_found = || ConfigEditError::PackageNotFound(package.to_string());
+    let unsupported = |path: String| ConfigEditError::UnsupportedLayout(path);
+
+    let pkgs = find_key(&parsed, 0..parsed.len(), 0, "packages").ok_or_else(not_found)?;
+    if !parsed[pkgs].is_block() {
+        return Err(unsupported("packages".into()));
+    }
+    let pkgs_end = block_end(&parsed, pkgs);
+    let pkg_indent = child_indent(&parsed, pkgs, pkgs_end).ok_or_else(not_found)?;
+
+    let pkg = find_key(&parsed, pkgs + 1..pkgs_end, pkg_indent, package).ok_or_else(not_found)?;
+    if !parsed[pkg].is_block() {
+        return Err(unsupported(format!("packages.{package}")));
+    }
+    let pkg_end = block_end(&parsed, pkg);
+    let maps_indent = child_indent(&parsed, pkg, pkg_end).unwrap_or(pkg_indent * 2);
+
+    // Lines in `start..end` are replaced by `new_lines`.
+    let (start, end, new_lines) = match find_key(&parsed, pkg + 1..pkg_end, maps_indent, "maps") {
+        Some(maps) if let Some(comment) = parsed[maps].empty_flow_map() => {
+            let comment = if comment.is_empty() {
+                String::new()
+            } else {
+                format!(" {comment}")
+            };
+            (
+                maps,
+                maps + 1,
+                vec![
+                    format!("{}maps:{comment}", " ".repeat(maps_indent)),
+                    yaml_entry(maps_indent + pkg_indent, src, dst),
+                ],
+            )
+        }
+        Some(maps) => {
+            if !parsed[maps].is_block() {
+                return Err(unsupported(format!("packages.{package}.maps")));
+            }
+            let maps_end = block_end(&parsed, maps);
+            let entry_indent =
+                child_indent(&parsed, maps, maps_end).unwrap_or(maps_indent + pkg_indent);
+
+            if find_key(&parsed, maps + 1..maps_end, entry_indent, src).is_some() {
+                return Err(ConfigEditError::MapAlreadyExists {
+                    package: package.to_string(),
+                    src: src.to_string(),
+                });
+            }
+
+            let insert_at = last_line(&parsed, maps, maps_end) + 1;
+            (
+                insert_at,
+                insert_at,
+                vec![yaml_entry(entry_indent, src, dst)],
+            )
+        }
+        None => {
+            let insert_at = last_line(&parsed, pkg, pkg_end) + 1;
+            (
+                insert_at,
+                insert_at,
+                vec![
+                    format!("{}maps:", " ".repeat(maps_indent)),
+                    yaml_entry(maps_indent + pkg_indent, src, dst),
+                ],
+            )
+        }
+    };
+
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut result = String::with_capacity(content.len() + 64);
+    for line in &lines[..start] {
+        result.push_str(line);
+    }
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push_str(newline);
+    }
+    for line in new_lines {
+        result.push_str(&line);
+        result.push_str(newline);
+    }
+    for line in &lines[end..] {
+        result.push_str(line);
+    }
+
+    Ok(result)
+}
+
+pub(super) fn yaml_entry(indent: usize, key: &str, value: &str) -> String {
+    format!(
+        "{}{}: {}",
+        " ".repeat(indent),
+        yaml_scalar(key),
+        yaml_scalar(value)
+    )
+}
+
+pub(super) fn yaml_scalar(s: &str) -> String {
+    serde_yaml_ng::to_string(s)
+        .map(|s| s.trim_end().to_string())
+        .unwrap_or_else(|_| format!("{s:?}"))
+}
+
+/// Find the line defining `key` at exactly `indent` in `range`.
+fn find_key(lines: &[YamlLine], range: Range<usize>, indent: usize, key: &str) -> Option<usize> {
+    range.into_iter().find(|&i| {
+        lines[i].is_content() && lines[i].indent == indent && lines[i].key.as_deref() == Some(key)
+    })
+}
+
+/// Index after the last line belonging to the block opened at `parent`.
+fn block_end(lines: &[YamlLine], parent: usize) -> usize {
+    let indent = lines[parent].indent;
+    (parent + 1..lines.len())
+        .find(|&i| lines[i].is_content() && lines[i].indent <= indent)
+        .unwrap_or(lines.len())
+}
+
+/// Indentation of the first child in the block opened at `parent`.
+fn child_indent(lines: &[YamlLine], parent: usize, end: usize) -> Option<usize> {
+    (parent + 1..end)
+        .find(|&i| lines[i].is_content())
+        .map(|i| lines[i].indent)
+}
+
+/// Index of the last line nested in the block opened at `parent`, which may be a comment.
+fn last_line(lines: &[YamlLine], parent: usize, end: usize) -> usize {
+    let indent = lines[parent].indent;
+    (parent + 1..end)
+        .rev()
+        .find(|&i| !lines[i].blank && lines[i].indent > indent)
+        .unwrap_or(parent)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::test_utils::prelude::*;
+
+    mod toml {
+        use super::*;
+
+        #[gtest]
+        fn append_to_maps() -> Result<()> {
+            let content = indoc! {r#"
+                # global comment
+                [vars]
+                CONFIG_DIR = "${HOME}/.config"
+
+                [packages.yazi]
+                kind = "local" # keep me
+
+                [packages.yazi.maps]
+                yazi = "${CONFIG_DIR}/yazi" # and me
+                "yazi.nu" = "${CONFIG_DIR}/nu/yazi.nu"
+
+                [packages.kitty.maps]
+                kitty = "${CONFIG_DIR}/kitty"
+            "#};
+
+            let result = add_map_to_toml(content, "yazi", "keymap.toml", "${CONFIG_DIR}/keymap")?;
+            expect_eq!(
+                result,
+                indoc! {r#"
+                    # global comment
+                    [vars]
+                    CONFIG_DIR = "${HOME}/.config"
+
+                    [packages.yazi]
+                    kind = "local" # keep me
+
+                    [packages.yazi.maps]
+                    yazi = "${CONFIG_DIR}/yazi" # and me
+                    "yazi.nu" = "${CONFIG_DIR}/nu/yazi.nu"
+                    "keymap.toml" = "${CONFIG_DIR}/keymap"
+
+                    [packages.kitty.maps]
+                    kitty = "${CONFIG_DIR}/kitty"
+                "#}
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn create_maps() -> Result<()> {
+            let content = indoc! {r#"
+                [packages.yazi]
+                kind = "local"
+
+                [packages.kitty.maps]
+                kitty = "dst"
+
+                [packages]
+                nu = { kind = "local" }
+            "#};
+
+            let result = add_map_to_toml(content, "yazi", "yazi", "${HOME}/yazi")?;
+            let result = add_map_to_toml(&result, "nu", "config.nu", "${HOME}/nu")?;
+            expect_eq!(
+                result,
+                indoc! {r#"
+                    [packages.yazi]
+                    kind = "local"
+
+                    [packages.yazi.maps]
+                    yazi = "${HOME}/yazi"
+
+                    [packages.kitty.maps]
+                    kitty = "dst"
+
+                    [packages]
+                    nu = { kind = "local" , maps = { "config.nu" = "${HOME}/nu" } }
+                "#}
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn inline_maps() -> Result<()> {
+            let content = indoc! {r#"
+                [packages.yazi]
+                maps = { yazi = "dst" }
+            "#};
+
+            let result = add_map_to_toml(content, "yazi", "other", "other_dst")?;
+            expect_eq!(
+                result,
+                indoc! {r#"
+                    [packages.yazi]
+                    maps = { yazi = "dst" , other = "other_dst" }
+                "#}
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn package_not_found() -> Result<()> {
+            let err = add_map_to_toml("[packages.yazi]\n", "kitty", "a", "b").unwrap_err();
+            expect_that!(err, pat!(ConfigEditError::PackageNotFound("kitty")));
+            Ok(())
+        }
+
+        #[gtest]
+        fn map_already_exists() -> Result<()> {
+            let content = "[packages.yazi.maps]\nyazi = \"dst\"\n";
+            let err = add_map_to_toml(content, "yazi", "yazi", "b").unwrap_err();
+            expect_that!(
+                err,
+                pat!(ConfigEditError::MapAlreadyExists {
+                    package: "yazi",
+                    src: "yazi"
+                })
+            );
+            Ok(())
+        }
+    }
+
+    mod yaml {
+        use super::*;
+
+        #[gtest]
+        fn append_to_maps() -> Result<()> {
+            let content = indoc! {r#"
+                # global comment
+                vars:
+                  CONFIG_DIR: ${HOME}/.config
+
+                packages:
+                  yazi:
+                    kind: local # keep me
+
+                    maps: # and me
+                      yazi.toml: ${CONFIG_DIR}/yazi/yazi.toml
+                      yazi.nu: ${CONFIG_DIR}/nu/ # a comment
+                                                 # that continues
+
+                  nu:
+                    maps:
+                      config.nu: ${CONFIG_DIR}/nushell/
+            "#};
+
+            let result = add_map_to_yaml(content, "yazi", "keymap.toml", "${CONFIG_DIR}/keymap")?;
+            expect_eq!(
+                result,
+                indoc! {r#"
+                    # global comment
+                    vars:
+                      CONFIG_DIR: ${HOME}/.config
+
+                    packages:
+                      yazi:
+                        kind: local # keep me
+
+                        maps: # and me
+                          yazi.toml: ${CONFIG_DIR}/yazi/yazi.toml
+                          yazi.nu: ${CONFIG_DIR}/nu/ # a comment
+                                                     # that continues
+                          keymap.toml: ${CONFIG_DIR}/keymap
+
+                      nu:
+                        maps:
+                          config.nu: ${CONFIG_DIR}/nushell/
+                "#}
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn append_to_last_package() -> Result<()> {
+            let content = "packages:\n    nu:\n        maps:\n            config.nu: dst";
+
+            let result = add_map_to_yaml(content, "nu", "env.nu", "env_dst")?;
+            expect_eq!(
+                result,
+                "packages:\n    nu:\n        maps:\n            config.nu: dst\n            env.nu: env_dst\n"
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn create_maps() -> Result<()> {
+            let content = indoc! {r#"
+                packages:
+                  yazi:
+                    kind: local
+                  nu:
+                  kitty:
+                    kind: local
+            "#};
+
+            let result = add_map_to_yaml(content, "yazi", "yazi", "${HOME}/yazi")?;
+            let result = add_map_to_yaml(&result, "nu", "config.nu", "${HOME}/nu")?;
+            expect_eq!(
+                result,
+                indoc! {r#"
+                    packages:
+                      yazi:
+                        kind: local
+                        maps:
+                          yazi: ${HOME}/yazi
+                      nu:
+                        maps:
+                          config.nu: ${HOME}/nu
+                      kitty:
+                        kind: local
+                "#}
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn quote_when_needed() -> Result<()> {
+            let content = "packages:\n  yazi:\n    maps:\n      \"a: b\": dst\n";
+
+            let result = add_map_to_yaml(content, "yazi", "#file", "dst: with colon")?;
+            let config = Config::from_yaml(&result)?;
+            expect_eq!(
+                config.packages["yazi"].maps,
+                [
+                    ("a: b".to_string(), "dst".into()),
+                    ("#file".to_string(), "dst: with colon".into()),
+                ]
+            );
+
+            let err = add_map_to_yaml(&result, "yazi", "a: b", "dst").unwrap_err();
+            expect_that!(err, pat!(ConfigEditError::MapAlreadyExists { .. }));
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn expand_empty_flow_maps() -> Result<()> {
+            let content = "packages:\n  yazi:\n    maps: {} # to fill\n  nu:\n    maps: {}\n";
+
+            let result = add_map_to_yaml(content, "yazi", "yazi", "dst")?;
+            let result = add_map_to_yaml(&result, "nu", "nu", "dst")?;
+            expect_eq!(
+                result,
+                "packages:\n  yazi:\n    maps: # to fill\n      yazi: dst\n  nu:\n    maps:\n      nu: dst\n"
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn flow_style_unsupported() -> Result<()> {
+            let content = "packages:\n  yazi:\n    maps: { yazi: dst }\n";
+            let err = add_map_to_yaml(content, "yazi", "a", "b").unwrap_err();
+            expect_that!(
+                err,
+                pat!(ConfigEditError::UnsupportedLayout("packages.yazi.maps"))
+            );
+            Ok(())
+        }
+
+        #[gtest]
+        fn package_not_found() -> Result<()> {
+            let content = "packages:\n  yazi:\n    maps:\n      yazi: dst\n";
+            let err = add_map_to_yaml(content, "kitty", "a", "b").unwrap_err();
+            expect_that!(err, pat!(ConfigEditError::PackageNotFound("kitty")));
+            Ok(())
+        }
+    }
+
+    #[gtest]
+    fn unsupported_file_format() -> Result<()> {
+        let td = TempDir::new()?.file("pkgs.json", "{}")?;
+        let err = Config::add_map(&td.join("pkgs.json"), "yazi", "a", "b").unwrap_err();
+        expect_that!(err, pat!(ConfigEditError::UnsupportedFileFormat(_)));
+        Ok(())
+    }
+}
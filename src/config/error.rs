@@ -17,6 +17,9 @@ pub enum VarsParseError {
 
     #[error("unknown variable '{0}' found at {1}")]
     UnknowndVar(String, usize),
+
+    #[error("unset environment variable '{0}' found at {1}")]
+    UnsetEnvVar(String, usize),
 }
 
 #[derive(Debug, Error)]
@@ -29,4 +32,13 @@ pub enum PkgsParseError {
 
     #[error("could not get filename from path '{0}'")]
     NoneFilename(String),
+
+    #[error("git package requires a 'url'")]
+    GitUrlMissing,
+
+    #[error("'{0}' is only allowed for git packages")]
+    GitFieldOnLocal(String),
+
+    #[error("only one of 'branch', 'tag' and 'commit' can be specified")]
+    GitRevisionConflict,
 }
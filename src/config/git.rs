@@ -0,0 +1,65 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitSource {
+    pub url: String,
+    pub revision: GitRevision,
+
+    /// Commit recorded in the lock file, takes precedence over `revision`.
+    pub locked: Option<String>,
+}
+
+impl GitSource {
+    /// The revision to check out, preferring the locked commit if any.
+    pub fn target(&self) -> String {
+        self.locked
+            .clone()
+            .unwrap_or_else(|| self.revision.target())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum GitRevision {
+    #[default]
+    Default,
+    Branch(String),
+    Tag(String),
+    Commit(String),
+}
+
+impl GitRevision {
+    /// The revision to check out in a cloned repository.
+    pub fn target(&self) -> String {
+        match self {
+            GitRevision::Default => "origin/HEAD".into(),
+            GitRevision::Branch(branch) => format!("origin/{branch}"),
+            GitRevision::Tag(tag) => format!("refs/tags/{tag}"),
+            GitRevision::Commit(commit) => commit.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::prelude::*;
+
+    #[gtest]
+    fn target() {
+        expect_eq!(GitRevision::Default.target(), "origin/HEAD");
+        expect_eq!(GitRevision::Branch("dev".into()).target(), "origin/dev");
+        expect_eq!(GitRevision::Tag("v1".into()).target(), "refs/tags/v1");
+        expect_eq!(GitRevision::Commit("abc123".into()).target(), "abc123");
+    }
+
+    #[gtest]
+    fn locked_target() {
+        let mut source = GitSource {
+            url: "url".into(),
+            revision: GitRevision::Branch("dev".into()),
+            locked: None,
+        };
+        expect_eq!(source.target(), "origin/dev");
+
+        source.locked = Some("abc123".into());
+        expect_eq!(source.target(), "abc123");
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::condition::matches_pattern;
+use super::de_map_as_vec::deserialize_map_as_vec;
+use super::read::parse_file;
+use super::{Config, ConfigError, Package, Profile};
+
+/// The part of the configuration an included file may define. Anything else, like the top-level
+/// `link`, only belongs in the main descriptor file and fails to parse here.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Fragment {
+    #[serde(default)]
+    include: Vec<String>,
+
+    #[serde(default, deserialize_with = "deserialize_map_as_vec")]
+    vars: Vec<(String, String)>,
+
+    #[serde(default)]
+    packages: BTreeMap<String, Package>,
+
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+impl Config {
+    /// Read the descriptor file at `path` and merge every file it includes, directly or through
+    /// another included file. Each file is read at most once.
+    pub fn read(path: &Path) -> Result<Self, ConfigError> {
+        let mut config = Self::read_file(path)?;
+        config.dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let mut var_files: BTreeMap<String, PathBuf> = config
+            .vars
+            .iter()
+            .map(|(name, _)| (name.clone(), path.to_path_buf()))
+            .collect();
+        let mut profile_files: BTreeMap<String, PathBuf> = config
+            .profiles
+            .keys()
+            .map(|name| (name.clone(), path.to_path_buf()))
+            .collect();
+        config.package_files = config
+            .packages
+            .keys()
+            .map(|name| (name.clone(), path.to_path_buf()))
+            .collect();
+
+        let mut visited = HashSet::from([fs::canonicalize(path)?]);
+        let mut pending = resolve_includes(path, &mem::take(&mut config.include))?;
+        while let Some(file) = pending.pop_front() {
+            let included = |source| ConfigError::Include {
+                path: file.clone(),
+                source: Box::new(source),
+            };
+
+            if !visited.insert(fs::canonicalize(&file).map_err(|e| included(e.into()))?) {
+                continue;
+            }
+            let mut fragment: Fragment = parse_file(&file).map_err(included)?;
+            pending.extend(resolve_includes(&file, &mem::take(&mut fragment.include))?);
+
+            for (name, value) in fragment.vars {
+                if let Some(other) = var_files.insert(name.clone(), file.clone()) {
+                    return Err(ConfigError::DuplicateVar {
+                        name,
+                        path: file,
+                        other,
+                    });
+                }
+                config.vars.push((name, value));
+            }
+            for (name, package) in fragment.packages {
+                if let Some(other) = config.package_files.insert(name.clone(), file.clone()) {
+                    return Err(ConfigError::DuplicatePackage {
+                        name,
+                        path: file,
+                        other,
+                    });
+                }
+                config.packages.insert(name, package);
+            }
+            for (name, profile) in fragment.profiles {
+                if let Some(other) = profile_files.insert(name.clone(), file.clone()) {
+                    return Err(ConfigError::DuplicateProfile {
+                        name,
+                        path: file,
+                        other,
+                    });
+                }
+                config.profiles.insert(name, profile);
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// Files matched by the `include` patterns of the file at `path`, in the order of the patterns
+/// and sorted by name for each pattern.
+fn resolve_includes(path: &Path, patterns: &[String]) -> Result<VecDeque<PathBuf>, ConfigError> {
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    let mut files = VecDeque::new();
+    for pattern in patterns {
+        let pattern = dir.join(pattern);
+        let file_pattern = pattern.file_name().map(|name| name.to_string_lossy());
+        let Some(file_pattern) = file_pattern.filter(|name| name.contains('*')) else {
+            files.push_back(pattern);
+            continue;
+        };
+
+        let parent = pattern.parent().unwrap_or(Path::new(""));
+        if !parent.is_dir() {
+            continue;
+        }
+        let mut matched = vec![];
+        for entry in fs::read_dir(parent)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if entry.path().is_file() && matches_pattern(&file_pattern, &name.to_string_lossy()) {
+                matched.push(entry.path());
+            }
+        }
+        matched.sort();
+        files.extend(matched);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::test_utils::prelude::*;
+
+    fn setup(files: &[(&str, &str)]) -> Result<TempDir> {
+        let td = TempDir::new()?;
+        for (path, content) in files {
+            let path = td.path().join(path);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, content)?;
+        }
+        Ok(td)
+    }
+
+    #[gtest]
+    fn merge_fragments() -> Result<()> {
+        let td = setup(&[
+            (
+                "pkgs.toml",
+                indoc! {r#"
+                    include = ["pkgs.d/*.toml", "extra.yaml"]
+
+                    [vars]
+                    CONFIG_DIR = "${HOME}/.config"
+
+                    [packages.a.maps]
+                    file = "${CONFIG_DIR}/a"
+                "#},
+            ),
+            (
+                "pkgs.d/b.toml",
+                indoc! {r#"
+                    [vars]
+                    B_DIR = "${CONFIG_DIR}/b"
+
+                    [packages.b.maps]
+                    file = "${B_DIR}/file"
+                "#},
+            ),
+            ("pkgs.d/c.toml", "[packages.c]\n"),
+            ("pkgs.d/ignored.txt", "not a descriptor"),
+            (
+                "extra.yaml",
+                "include: [pkgs.toml]\nprofiles:\n  laptop:\n    hosts: [laptop]\n",
+            ),
+        ])?;
+
+        let config = Config::read(&td.path().join("pkgs.toml"))?;
+        expect_eq!(
+            config
+                .vars
+                .iter()
+                .map(|(k, _)| k.as_str())
+                .collect::<Vec<_>>(),
+            ["CONFIG_DIR", "B_DIR"]
+        );
+        expect_eq!(config.packages.keys().collect::<Vec<_>>(), ["a", "b", "c"]);
+        expect_eq!(config.profiles.keys().collect::<Vec<_>>(), ["laptop"]);
+        expect_eq!(
+            config.package_files["b"],
+            td.path().join("pkgs.d").join("b.toml")
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn duplicate_package() -> Result<()> {
+        let td = setup(&[
+            ("pkgs.toml", "include = [\"other.toml\"]\n[packages.a]\n"),
+            ("other.toml", "[packages.a]\n"),
+        ])?;
+
+        let err = Config::read(&td.path().join("pkgs.toml")).unwrap_err();
+        expect_that!(err, pat!(ConfigError::DuplicatePackage { name: "a", .. }));
+        expect_eq!(
+            err.to_string(),
+            format!(
+                "package 'a' in '{}' is already defined in '{}'",
+                td.path().join("other.toml").display(),
+                td.path().join("pkgs.toml").display()
+            )
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn duplicate_var() -> Result<()> {
+        let td = setup(&[
+            (
+                "pkgs.toml",
+                "include = [\"other.toml\"]\n[vars]\nA = \"a\"\n",
+            ),
+            ("other.toml", "[vars]\nA = \"b\"\n"),
+        ])?;
+
+        let err = Config::read(&td.path().join("pkgs.toml")).unwrap_err();
+        expect_that!(err, pat!(ConfigError::DuplicateVar { name: "A", .. }));
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn fragment_parse_error() -> Result<()> {
+        let td = setup(&[
+            ("pkgs.toml", "include = [\"other.toml\"]\n"),
+            ("other.toml", "invalid toml content"),
+        ])?;
+
+        let err = Config::read(&td.path().join("pkgs.toml")).unwrap_err();
+        expect_true!(matches!(
+            &err,
+            ConfigError::Include { source, .. } if matches!(**source, ConfigError::TomlParse(_))
+        ));
+        expect_that!(
+            err.to_string(),
+            starts_with(format!(
+                "failed to read included file '{}': TOML parse error",
+                td.path().join("other.toml").display()
+            ))
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn link_in_fragment() -> Result<()> {
+        let td = setup(&[
+            ("pkgs.toml", "include = [\"other.toml\"]\n"),
+            ("other.toml", "link = \"relative\"\n"),
+        ])?;
+
+        let err = Config::read(&td.path().join("pkgs.toml")).unwrap_err();
+        expect_true!(matches!(
+            &err,
+            ConfigError::Include { source, .. } if matches!(**source, ConfigError::TomlParse(_))
+        ));
+        expect_that!(
+            err.to_string(),
+            all!(
+                starts_with(format!(
+                    "failed to read included file '{}'",
+                    td.path().join("other.toml").display()
+                )),
+                contains_substring("unknown field `link`")
+            )
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn missing_file() -> Result<()> {
+        let td = setup(&[(
+            "pkgs.toml",
+            "include = [\"missing.toml\", \"none/*.toml\"]\n",
+        )])?;
+
+        let err = Config::read(&td.path().join("pkgs.toml")).unwrap_err();
+        expect_true!(matches!(
+            &err,
+            ConfigError::Include { source, .. } if matches!(**source, ConfigError::Io(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -1,48 +1,106 @@
+mod check;
+mod condition;
 mod de_map_as_vec;
+mod edit;
 mod error;
+mod git;
+mod include;
 mod named_package;
+mod profile;
 mod read;
+mod scaffold;
+mod validate;
 mod var;
 
 use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 use schemars::JsonSchema;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use de_map_as_vec::deserialize_map_as_vec;
 
+pub use check::{Problem, ProblemKind};
+pub use condition::{Condition, ConditionalTarget, MapTarget};
+pub use edit::ConfigEditError;
 pub use error::{PkgsParseError, VarsBuildError, VarsParseError};
+pub use git::{GitRevision, GitSource};
 pub use named_package::NamedPackage;
+pub use profile::{PackageOverride, Profile, ProfileError};
 pub use read::ConfigError;
+pub use validate::{DstConflict, ValidateError, find_dst_conflicts};
 pub use var::VarMap;
 
 fn empty_map() -> BTreeMap<String, String> {
     BTreeMap::new()
 }
 
-#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
+    /// Other descriptor files merged into this one, relative to it, where `*` in the file name
+    /// matches any characters
+    #[serde(default)]
+    pub include: Vec<String>,
+
     #[serde(default, deserialize_with = "deserialize_map_as_vec")]
     #[schemars(default = "empty_map", with = "BTreeMap<String, String>")]
     pub vars: Vec<(String, String)>,
 
+    /// How symlinks are written, unless a package overrides it
+    #[serde(default)]
+    pub link: LinkStyle,
+
+    #[serde(default)]
     pub packages: BTreeMap<String, Package>,
+
+    /// Overrides selected with `--profile` or by hostname
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+
+    /// The descriptor file each package is defined in, filled by [`Config::read`]
+    #[serde(skip)]
+    pub package_files: BTreeMap<String, PathBuf>,
+
+    /// Directory of the main descriptor file, which package directories are relative to
+    #[serde(skip)]
+    pub dir: PathBuf,
 }
 
-#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Package {
     #[serde(default)]
     pub kind: PackageType,
 
+    /// Repository url of a git package
+    pub url: Option<String>,
+
+    /// Branch to track, conflicts with `tag` and `commit`
+    pub branch: Option<String>,
+
+    /// Tag to pin, conflicts with `branch` and `commit`
+    pub tag: Option<String>,
+
+    /// Commit to pin, conflicts with `branch` and `tag`
+    pub commit: Option<String>,
+
     #[serde(default, deserialize_with = "deserialize_map_as_vec")]
     #[schemars(default = "empty_map", with = "BTreeMap<String, String>")]
     pub vars: Vec<(String, String)>,
 
     #[serde(default, deserialize_with = "deserialize_map_as_vec")]
-    #[schemars(default = "empty_map", with = "BTreeMap<String, String>")]
-    pub maps: Vec<(String, String)>,
+    #[schemars(default = "empty_map", with = "BTreeMap<String, MapTarget>")]
+    pub maps: Vec<(String, MapTarget)>,
+
+    /// Skip the package unless the condition holds
+    pub when: Option<Condition>,
+
+    /// How symlinks of this package are written, defaults to the global `link`
+    pub link: Option<LinkStyle>,
+
+    /// Set to `false` to skip the package unless a profile enables it
+    pub enabled: Option<bool>,
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, JsonSchema, Default, PartialEq, Eq)]
@@ -50,4 +108,21 @@ pub struct Package {
 pub enum PackageType {
     #[default]
     Local,
+    Git,
+}
+
+/// Whether a symlink stores the absolute path of its source, or the path relative to the
+/// directory containing the symlink.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, JsonSchema, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkStyle {
+    #[default]
+    Absolute,
+    Relative,
+}
+
+impl LinkStyle {
+    pub fn is_absolute(&self) -> bool {
+        *self == LinkStyle::Absolute
+    }
 }
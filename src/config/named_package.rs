@@ -1,15 +1,43 @@
 use std::path::Path;
 
-use super::{Config, PkgsParseError, VarMap};
+use super::{Config, GitRevision, GitSource, LinkStyle, PkgsParseError, VarMap};
 use crate::config::{Package, PackageType};
+use crate::meta::{GIT_DIR, PKGS_DIR};
+use crate::trace::PkgLock;
 
 impl Config {
     pub fn get(&self, name: &str) -> Result<NamedPackage, PkgsParseError> {
-        NamedPackage::try_new(
-            name,
-            self.packages[name].clone(),
-            VarMap::try_new(&self.vars)?, // PERF: varmap will be built multiple times here
-        )
+        let mut package = self.packages[name].clone();
+        package.link.get_or_insert(self.link);
+        // PERF: varmap will be built multiple times here
+        let vars = self.with_package_vars(name, VarMap::try_new(&self.vars)?);
+        NamedPackage::try_new(name, package, vars)
+    }
+
+    /// Add the built-in `PKG_NAME` and `PKG_DIR` of the package `name` to `vars`, unless the
+    /// global vars declare them.
+    pub fn with_package_vars(&self, name: &str, mut vars: VarMap) -> VarMap {
+        let dir = self
+            .dir
+            .join(package_directory(name, self.packages[name].kind));
+        let builtins = [
+            ("PKG_NAME", name.to_string()),
+            ("PKG_DIR", dir.to_string_lossy().into_owned()),
+        ];
+        for (var, value) in builtins {
+            if !self.vars.iter().any(|(k, _)| k == var) {
+                vars.set(var, value);
+            }
+        }
+        vars
+    }
+}
+
+/// Directory of the package `name`, relative to the descriptor file.
+fn package_directory(name: &str, kind: PackageType) -> String {
+    match kind {
+        PackageType::Local => name.to_string(),
+        PackageType::Git => format!("{PKGS_DIR}/{GIT_DIR}/{name}"),
     }
 }
 
@@ -17,45 +45,110 @@ impl Config {
 pub struct NamedPackage {
     name: String,
     kind: PackageType,
+    git: Option<GitSource>,
     maps: Vec<(String, String)>,
+    link: LinkStyle,
+    active: bool,
+    skipped: Vec<String>,
 }
 
 impl NamedPackage {
     pub fn try_new(name: &str, package: Package, mut vars: VarMap) -> Result<Self, PkgsParseError> {
+        let link = package.link.unwrap_or_default();
+        // nothing else is resolved, vars may rely on what the condition checks
+        if package.when.as_ref().is_some_and(|when| !when.holds()) {
+            return Ok(Self {
+                name: name.to_string(),
+                kind: package.kind,
+                git: None,
+                maps: vec![],
+                link,
+                active: false,
+                skipped: vec![],
+            });
+        }
+
         vars.extends(&package.vars)?;
 
-        let maps = package
-            .maps
-            .into_iter()
-            .map(|(k, v)| {
-                let mut v = vars.parse(&v)?;
-
-                let k_path = Path::new(&k);
-                if v.ends_with('/') {
-                    v.push_str(
-                        k_path
-                            .file_name()
-                            .ok_or_else(|| PkgsParseError::NoneFilename(k.clone()))?
-                            .to_string_lossy()
-                            .as_ref(),
-                    );
-                }
-
-                Ok((k, v))
-            })
-            .collect::<Result<Vec<_>, PkgsParseError>>()?;
+        let git = Self::parse_git_source(&package, &vars)?;
+
+        let mut maps = vec![];
+        let mut skipped = vec![];
+        for (k, v) in package.maps {
+            if v.when().is_some_and(|when| !when.holds()) {
+                skipped.push(k);
+                continue;
+            }
+            let v = Self::parse_map(&vars, &k, v.dst())?;
+            maps.push((k, v));
+        }
 
         Ok(Self {
             name: name.to_string(),
             kind: package.kind,
+            git,
             maps,
+            link,
+            active: true,
+            skipped,
         })
     }
 
-    pub fn get_directory(&self) -> String {
-        match self.kind() {
-            PackageType::Local => self.name.to_string(),
+    /// Resolve the destination `dst` of the source `src`, appending the file name of `src` if
+    /// `dst` ends with a slash.
+    pub(super) fn parse_map(vars: &VarMap, src: &str, dst: &str) -> Result<String, PkgsParseError> {
+        let mut dst = vars.parse(dst)?;
+        if dst.ends_with('/') {
+            dst.push_str(
+                Path::new(src)
+                    .file_name()
+                    .ok_or_else(|| PkgsParseError::NoneFilename(src.to_string()))?
+                    .to_string_lossy()
+                    .as_ref(),
+            );
         }
+        Ok(dst)
+    }
+
+    pub(super) fn parse_git_source(
+        package: &Package,
+        vars: &VarMap,
+    ) -> Result<Option<GitSource>, PkgsParseError> {
+        let revisions = [
+            ("branch", &package.branch),
+            ("tag", &package.tag),
+            ("commit", &package.commit),
+        ];
+
+        if package.kind == PackageType::Local {
+            if package.url.is_some() {
+                return Err(PkgsParseError::GitFieldOnLocal("url".into()));
+            }
+            if let Some((field, _)) = revisions.iter().find(|(_, v)| v.is_some()) {
+                return Err(PkgsParseError::GitFieldOnLocal(field.to_string()));
+            }
+            return Ok(None);
+        }
+
+        let url = package.url.as_ref().ok_or(PkgsParseError::GitUrlMissing)?;
+
+        let revision = match (&package.branch, &package.tag, &package.commit) {
+            (None, None, None) => GitRevision::Default,
+            (Some(branch), None, None) => GitRevision::Branch(branch.clone()),
+            (None, Some(tag), None) => GitRevision::Tag(tag.clone()),
+            (None, None, Some(commit)) => GitRevision::Commit(commit.clone()),
+            _ => return Err(PkgsParseError::GitRevisionConflict),
+        };
+
+        Ok(Some(GitSource {
+            url: vars.parse(url)?,
+            revision,
+            locked: None,
+        }))
+    }
+
+    pub fn get_directory(&self) -> String {
+        package_directory(&self.name, self.kind)
     }
 
     pub fn name(&self) -> &str {
@@ -66,10 +159,39 @@ impl NamedPackage {
         self.kind
     }
 
+    pub fn git(&self) -> Option<&GitSource> {
+        self.git.as_ref()
+    }
+
+    /// Pin the package to the revision recorded in the lock file.
+    ///
+    /// The lock is ignored if the package is not a git package or its url has changed.
+    pub fn lock(&mut self, lock: &PkgLock) {
+        if let Some(git) = &mut self.git
+            && git.url == lock.url
+        {
+            git.locked = Some(lock.revision.clone());
+        }
+    }
+
     pub fn maps(&self) -> &[(String, String)] {
         &self.maps
     }
 
+    pub fn link(&self) -> LinkStyle {
+        self.link
+    }
+
+    /// Whether the `when` condition of the package holds. Inactive packages are skipped.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Sources of the maps left out of [`NamedPackage::maps`] because their condition is false.
+    pub fn skipped_maps(&self) -> &[String] {
+        &self.skipped
+    }
+
     #[cfg(test)]
     pub fn insert_map(&mut self, key: impl AsRef<str>, value: impl AsRef<str>) {
         self.maps
@@ -87,6 +209,7 @@ mod tests {
     use std::collections::BTreeMap;
 
     use super::*;
+    use crate::config::{Condition, ConditionalTarget, MapTarget};
     use crate::{fs::home_dir, test_utils::prelude::*};
 
     fn setup() -> Config {
@@ -102,14 +225,19 @@ mod tests {
                 kind: PackageType::Local,
                 vars: vec![],
                 maps: vec![
-                    ("app_dir".to_string(), "${APP_DIR}".to_string()),
-                    ("path".to_string(), "/usr/local/${MY_VAR2}".to_string()),
-                    ("config".to_string(), "${MY_VAR1}_config".to_string()),
+                    ("app_dir".to_string(), "${APP_DIR}".into()),
+                    ("path".to_string(), "/usr/local/${MY_VAR2}".into()),
+                    ("config".to_string(), "${MY_VAR1}_config".into()),
                 ],
+                ..Default::default()
             },
         )]);
 
-        Config { vars, packages }
+        Config {
+            vars,
+            packages,
+            ..Default::default()
+        }
     }
 
     #[gtest]
@@ -136,6 +264,39 @@ mod tests {
         Ok(())
     }
 
+    #[gtest]
+    fn package_vars() -> Result<()> {
+        let mut config = setup();
+        config.dir = "/pkgs".into();
+        let package = config.packages.get_mut("test_pkg").unwrap();
+        package.vars = vec![("PKG_VAR".into(), "${PKG_NAME}@${PKG_DIR}".into())];
+        package.maps = vec![("file".into(), "/dst/${PKG_VAR}".into())];
+
+        let dir = Path::new("/pkgs").join("test_pkg");
+        let pkg = config.get("test_pkg")?;
+        expect_eq!(pkg.maps()[0].1, format!("/dst/test_pkg@{}", dir.display()));
+
+        config.vars.push(("PKG_NAME".into(), "global".into()));
+        let pkg = config.get("test_pkg")?;
+        expect_eq!(pkg.maps()[0].1, format!("/dst/global@{}", dir.display()));
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn link_style() -> Result<()> {
+        let mut config = setup();
+        expect_eq!(config.get("test_pkg")?.link(), LinkStyle::Absolute);
+
+        config.link = LinkStyle::Relative;
+        expect_eq!(config.get("test_pkg")?.link(), LinkStyle::Relative);
+
+        config.packages.get_mut("test_pkg").unwrap().link = Some(LinkStyle::Absolute);
+        expect_eq!(config.get("test_pkg")?.link(), LinkStyle::Absolute);
+
+        Ok(())
+    }
+
     #[gtest]
     fn local_vars() -> Result<()> {
         let mut config = setup();
@@ -183,7 +344,7 @@ mod tests {
             .get_mut("test_pkg")
             .unwrap()
             .maps
-            .push(("bad".to_string(), "${UNKNOWN}".to_string()));
+            .push(("bad".to_string(), "${UNKNOWN}".into()));
 
         let err = config.get("test_pkg").unwrap_err();
         expect_that!(err, pat!(PkgsParseError::VarsParse(_)));
@@ -191,6 +352,32 @@ mod tests {
         Ok(())
     }
 
+    #[gtest]
+    fn conditions() -> Result<()> {
+        let never = Condition {
+            os: vec!["none".into()],
+            ..Default::default()
+        };
+        let mut config = setup();
+        let package = config.packages.get_mut("test_pkg").unwrap();
+        package.maps[0].1 = MapTarget::Conditional(ConditionalTarget {
+            dst: "${UNKNOWN}".into(),
+            when: never.clone(),
+        });
+
+        let pkg = config.get("test_pkg")?;
+        expect_true!(pkg.is_active());
+        expect_eq!(pkg.skipped_maps().to_vec(), ["app_dir"]);
+        expect_eq!(pkg.maps().len(), 2);
+
+        config.packages.get_mut("test_pkg").unwrap().when = Some(never);
+        let pkg = config.get("test_pkg")?;
+        expect_false!(pkg.is_active());
+        expect_that!(pkg.maps(), is_empty());
+
+        Ok(())
+    }
+
     mod trailing_slash {
         use super::*;
 
@@ -205,11 +392,16 @@ mod tests {
                 Package {
                     kind: PackageType::Local,
                     vars: vec![],
-                    maps: vec![(src.to_string(), dst.to_string())],
+                    maps: vec![(src.to_string(), dst.into())],
+                    ..Default::default()
                 },
             )]);
 
-            Config { vars, packages }
+            Config {
+                vars,
+                packages,
+                ..Default::default()
+            }
         }
 
         #[gtest]
@@ -260,5 +452,143 @@ mod tests {
         }
     }
 
+    mod git_package {
+        use super::*;
+
+        fn setup(package: Package) -> Config {
+            let vars = vec![("REPO_DIR".to_string(), "/path/to/repos".to_string())];
+            let packages = BTreeMap::from_iter([("git_pkg".to_string(), package)]);
+            Config {
+                vars,
+                packages,
+                ..Default::default()
+            }
+        }
+
+        fn git_package() -> Package {
+            Package {
+                kind: PackageType::Git,
+                url: Some("${REPO_DIR}/plugin.git".to_string()),
+                ..Default::default()
+            }
+        }
+
+        #[gtest]
+        fn it_works() -> Result<()> {
+            let config = setup(git_package());
+            let pkg = config.get("git_pkg")?;
+
+            expect_eq!(pkg.kind(), PackageType::Git);
+            expect_eq!(pkg.get_directory(), ".pkgs/git/git_pkg");
+            expect_eq!(
+                pkg.git(),
+                Some(&GitSource {
+                    url: "/path/to/repos/plugin.git".into(),
+                    revision: GitRevision::Default,
+                    locked: None,
+                })
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn revision() -> Result<()> {
+            let cases = [
+                (
+                    Package {
+                        branch: Some("dev".into()),
+                        ..git_package()
+                    },
+                    GitRevision::Branch("dev".into()),
+                ),
+                (
+                    Package {
+                        tag: Some("v1".into()),
+                        ..git_package()
+                    },
+                    GitRevision::Tag("v1".into()),
+                ),
+                (
+                    Package {
+                        commit: Some("abc123".into()),
+                        ..git_package()
+                    },
+                    GitRevision::Commit("abc123".into()),
+                ),
+            ];
+
+            for (package, revision) in cases {
+                let pkg = setup(package).get("git_pkg")?;
+                expect_eq!(pkg.git().unwrap().revision, revision);
+            }
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn lock() -> Result<()> {
+            let config = setup(git_package());
+
+            let mut pkg = config.get("git_pkg")?;
+            pkg.lock(&PkgLock {
+                url: "/path/to/repos/plugin.git".into(),
+                revision: "abc123".into(),
+            });
+            expect_eq!(pkg.git().unwrap().locked.as_deref(), Some("abc123"));
+
+            let mut pkg = config.get("git_pkg")?;
+            pkg.lock(&PkgLock {
+                url: "/path/to/repos/another.git".into(),
+                revision: "abc123".into(),
+            });
+            expect_eq!(pkg.git().unwrap().locked, None);
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn url_missing() -> Result<()> {
+            let config = setup(Package {
+                url: None,
+                ..git_package()
+            });
+
+            let err = config.get("git_pkg").unwrap_err();
+            expect_that!(err, pat!(PkgsParseError::GitUrlMissing));
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn revision_conflict() -> Result<()> {
+            let config = setup(Package {
+                branch: Some("dev".into()),
+                tag: Some("v1".into()),
+                ..git_package()
+            });
+
+            let err = config.get("git_pkg").unwrap_err();
+            expect_that!(err, pat!(PkgsParseError::GitRevisionConflict));
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn git_field_on_local() -> Result<()> {
+            let config = setup(Package {
+                kind: PackageType::Local,
+                url: None,
+                tag: Some("v1".into()),
+                ..Default::default()
+            });
+
+            let err = config.get("git_pkg").unwrap_err();
+            expect_that!(err, pat!(PkgsParseError::GitFieldOnLocal("tag")));
+
+            Ok(())
+        }
+    }
+
     mod local_vars {}
 }
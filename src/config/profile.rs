@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::de_map_as_vec::deserialize_map_as_vec;
+use super::{Config, MapTarget, empty_map};
+
+/// Overrides applied on top of the configuration on some machines.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Hostnames on which the profile is used when `--profile` is not given
+    #[serde(default)]
+    pub hosts: Vec<String>,
+
+    /// Global vars to override or add
+    #[serde(default, deserialize_with = "deserialize_map_as_vec")]
+    #[schemars(default = "empty_map", with = "BTreeMap<String, String>")]
+    pub vars: Vec<(String, String)>,
+
+    #[serde(default)]
+    pub packages: BTreeMap<String, PackageOverride>,
+}
+
+/// Overrides of a single package in a profile.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PackageOverride {
+    /// Enable or disable the package
+    pub enabled: Option<bool>,
+
+    /// Package vars to override or add
+    #[serde(default, deserialize_with = "deserialize_map_as_vec")]
+    #[schemars(default = "empty_map", with = "BTreeMap<String, String>")]
+    pub vars: Vec<(String, String)>,
+
+    /// Maps to replace, by source, or add
+    #[serde(default, deserialize_with = "deserialize_map_as_vec")]
+    #[schemars(default = "empty_map", with = "BTreeMap<String, MapTarget>")]
+    pub maps: Vec<(String, MapTarget)>,
+}
+
+#[derive(Debug, Error)]
+pub enum ProfileError {
+    #[error("profile '{0}' not found")]
+    NotFound(String),
+
+    #[error("package '{package}' in profile '{profile}' not found")]
+    PackageNotFound { profile: String, package: String },
+
+    #[error("host '{host}' matches several profiles: {}", .profiles.join(", "))]
+    AmbiguousHost { host: String, profiles: Vec<String> },
+}
+
+impl Config {
+    /// Apply the profile `name`, or the one listing `host` if no name is given, and drop the
+    /// disabled packages. Returns the name of the profile in effect.
+    pub fn apply_profile(
+        &mut self,
+        name: Option<&str>,
+        host: Option<&str>,
+    ) -> Result<Option<String>, ProfileError> {
+        let Some(name) = self.select_profile(name, host)? else {
+            self.packages.retain(|_, pkg| pkg.enabled != Some(false));
+            return Ok(None);
+        };
+        let profile = self.profiles[&name].clone();
+
+        merge_pairs(&mut self.vars, &profile.vars);
+        for (pkg_name, over) in profile.packages {
+            let Some(package) = self.packages.get_mut(&pkg_name) else {
+                return Err(ProfileError::PackageNotFound {
+                    profile: name,
+                    package: pkg_name,
+                });
+            };
+            if over.enabled.is_some() {
+                package.enabled = over.enabled;
+            }
+            merge_pairs(&mut package.vars, &over.vars);
+            merge_pairs(&mut package.maps, &over.maps);
+        }
+
+        self.packages.retain(|_, pkg| pkg.enabled != Some(false));
+        Ok(Some(name))
+    }
+
+    fn select_profile(
+        &self,
+        name: Option<&str>,
+        host: Option<&str>,
+    ) -> Result<Option<String>, ProfileError> {
+        if let Some(name) = name {
+            if !self.profiles.contains_key(name) {
+                return Err(ProfileError::NotFound(name.to_string()));
+            }
+            return Ok(Some(name.to_string()));
+        }
+
+        let Some(host) = host else {
+            return Ok(None);
+        };
+        let matched: Vec<_> = self
+            .profiles
+            .iter()
+            .filter(|(_, profile)| profile.hosts.iter().any(|h| h == host))
+            .map(|(name, _)| name.clone())
+            .collect();
+        match matched.len() {
+            0 => Ok(None),
+            1 => Ok(matched.into_iter().next()),
+            _ => Err(ProfileError::AmbiguousHost {
+                host: host.to_string(),
+                profiles: matched,
+            }),
+        }
+    }
+}
+
+/// Replace the values of the keys already in `base` in place, keeping their order, and append
+/// the others.
+fn merge_pairs<V: Clone>(base: &mut Vec<(String, V)>, overrides: &[(String, V)]) {
+    for (key, value) in overrides {
+        match base.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.clone(),
+            None => base.push((key.clone(), value.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::test_utils::prelude::*;
+
+    fn config() -> Config {
+        Config::from_toml(indoc! {r#"
+            [vars]
+            CONFIG_DIR = "${HOME}/.config"
+            APP_DIR = "${CONFIG_DIR}/app"
+
+            [packages.a.maps]
+            file = "${APP_DIR}/file"
+            dir = "${APP_DIR}/dir"
+
+            [packages.b]
+            enabled = false
+
+            [packages.b.maps]
+            file = "/dst/b"
+
+            [profiles.laptop]
+            hosts = ["my-laptop"]
+
+            [profiles.laptop.vars]
+            CONFIG_DIR = "/laptop/config"
+            EXTRA = "extra"
+
+            [profiles.laptop.packages.a]
+            enabled = true
+
+            [profiles.laptop.packages.a.maps]
+            dir = "${EXTRA}/dir"
+            new = "/dst/new"
+
+            [profiles.laptop.packages.b]
+            enabled = true
+
+            [profiles.server]
+            hosts = ["server"]
+
+            [profiles.server.packages.a]
+            enabled = false
+        "#})
+        .unwrap()
+    }
+
+    fn pairs<V: From<&'static str>>(pairs: &[(&str, &'static str)]) -> Vec<(String, V)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), (*v).into()))
+            .collect()
+    }
+
+    #[gtest]
+    fn no_profile() -> Result<()> {
+        let mut config = config();
+        expect_eq!(config.apply_profile(None, Some("unknown"))?, None);
+
+        expect_eq!(config.packages.keys().collect::<Vec<_>>(), ["a"]);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn override_vars_and_maps() -> Result<()> {
+        let mut config = config();
+        expect_eq!(
+            config.apply_profile(Some("laptop"), None)?,
+            Some("laptop".into())
+        );
+
+        expect_eq!(
+            config.vars,
+            pairs::<String>(&[
+                ("CONFIG_DIR", "/laptop/config"),
+                ("APP_DIR", "${CONFIG_DIR}/app"),
+                ("EXTRA", "extra"),
+            ])
+        );
+        expect_eq!(
+            config.packages["a"].maps,
+            pairs::<MapTarget>(&[
+                ("file", "${APP_DIR}/file"),
+                ("dir", "${EXTRA}/dir"),
+                ("new", "/dst/new"),
+            ])
+        );
+        expect_eq!(config.packages.keys().collect::<Vec<_>>(), ["a", "b"]);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn detect_from_host() -> Result<()> {
+        let mut config = config();
+        expect_eq!(
+            config.apply_profile(None, Some("server"))?,
+            Some("server".into())
+        );
+
+        expect_that!(config.packages, is_empty());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn name_over_host() -> Result<()> {
+        let mut config = config();
+        expect_eq!(
+            config.apply_profile(Some("laptop"), Some("server"))?,
+            Some("laptop".into())
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn not_found() {
+        let err = config().apply_profile(Some("desktop"), None).unwrap_err();
+        expect_that!(err, pat!(ProfileError::NotFound("desktop")));
+    }
+
+    #[gtest]
+    fn package_not_found() {
+        let mut config = config();
+        config
+            .profiles
+            .get_mut("server")
+            .unwrap()
+            .packages
+            .insert("c".into(), PackageOverride::default());
+
+        let err = config.apply_profile(Some("server"), None).unwrap_err();
+        expect_that!(
+            err,
+            pat!(ProfileError::PackageNotFound {
+                profile: "server",
+                package: "c",
+            })
+        );
+    }
+
+    #[gtest]
+    fn ambiguous_host() {
+        let mut config = config();
+        config
+            .profiles
+            .get_mut("server")
+            .unwrap()
+            .hosts
+            .push("my-laptop".into());
+
+        let err = config.apply_profile(None, Some("my-laptop")).unwrap_err();
+        expect_eq!(
+            err.to_string(),
+            "host 'my-laptop' matches several profiles: laptop, server"
+        );
+    }
+}
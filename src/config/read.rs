@@ -2,6 +2,7 @@ use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
+use serde::de::DeserializeOwned;
 use serde_yaml_ng::Error as YamlDeError;
 use thiserror::Error;
 use toml::de::Error as TomlDeError;
@@ -21,17 +22,39 @@ pub enum ConfigError {
 
     #[error("unsupported file format: {0}")]
     UnsupportedFileFormat(PathBuf),
+
+    #[error("failed to read included file '{path}': {source}")]
+    Include {
+        path: PathBuf,
+        source: Box<ConfigError>,
+    },
+
+    #[error("package '{name}' in '{path}' is already defined in '{other}'")]
+    DuplicatePackage {
+        name: String,
+        path: PathBuf,
+        other: PathBuf,
+    },
+
+    #[error("var '{name}' in '{path}' is already defined in '{other}'")]
+    DuplicateVar {
+        name: String,
+        path: PathBuf,
+        other: PathBuf,
+    },
+
+    #[error("profile '{name}' in '{path}' is already defined in '{other}'")]
+    DuplicateProfile {
+        name: String,
+        path: PathBuf,
+        other: PathBuf,
+    },
 }
 
 impl Config {
-    pub fn read(path: &Path) -> Result<Self, ConfigError> {
-        let content = fs::read_to_string(path)?;
-
-        match path.extension().and_then(|s| s.to_str()) {
-            Some("toml") => Self::from_toml(&content).map_err(ConfigError::TomlParse),
-            Some("yaml") | Some("yml") => Self::from_yaml(&content).map_err(ConfigError::YamlParse),
-            _ => Err(ConfigError::UnsupportedFileFormat(path.to_path_buf())),
-        }
+    /// Read a single descriptor file, leaving its `include` unresolved.
+    pub(super) fn read_file(path: &Path) -> Result<Self, ConfigError> {
+        parse_file(path)
     }
 
     pub fn from_toml(content: &str) -> Result<Self, TomlDeError> {
@@ -43,6 +66,19 @@ impl Config {
     }
 }
 
+/// Parse a TOML or YAML file, chosen by its extension.
+pub(super) fn parse_file<T: DeserializeOwned>(path: &Path) -> Result<T, ConfigError> {
+    let content = fs::read_to_string(path)?;
+
+    match path.extension().and_then(|s| s.to_str()) {
+        Some("toml") => toml::from_str(&content).map_err(ConfigError::TomlParse),
+        Some("yaml") | Some("yml") => {
+            serde_yaml_ng::from_str(&content).map_err(ConfigError::YamlParse)
+        }
+        _ => Err(ConfigError::UnsupportedFileFormat(path.to_path_buf())),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use indoc::indoc;
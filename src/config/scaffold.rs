@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use toml_edit::{DocumentMut, Item, Table};
+
+use super::edit::{yaml_entry, yaml_scalar};
+use super::{Config, ConfigEditError};
+
+impl Config {
+    /// Render a starter configuration file for `path` where each of `packages` is a local
+    /// package with the given maps.
+    pub fn scaffold(
+        path: &Path,
+        packages: &BTreeMap<String, Vec<(String, String)>>,
+    ) -> Result<String, ConfigEditError> {
+        match path.extension().and_then(|s| s.to_str()) {
+            Some("toml") => Ok(scaffold_toml(packages)),
+            Some("yaml") | Some("yml") => Ok(scaffold_yaml(packages)),
+            _ => Err(ConfigEditError::UnsupportedFileFormat(path.to_path_buf())),
+        }
+    }
+}
+
+fn scaffold_toml(packages: &BTreeMap<String, Vec<(String, String)>>) -> String {
+    let mut pkgs = Table::new();
+    pkgs.set_implicit(!packages.is_empty());
+
+    for (name, maps) in packages {
+        let mut pkg = Table::new();
+        pkg.insert("kind", toml_edit::value("local"));
+
+        let mut table = Table::new();
+        for (src, dst) in maps {
+            table.insert(src, toml_edit::value(dst));
+        }
+        pkg.insert("maps", Item::Table(table));
+
+        pkgs.insert(name, Item::Table(pkg));
+    }
+
+    let mut doc = DocumentMut::new();
+    doc.insert("packages", Item::Table(pkgs));
+    doc.to_string()
+}
+
+fn scaffold_yaml(packages: &BTreeMap<String, Vec<(String, String)>>) -> String {
+    if packages.is_empty() {
+        return "packages: {}\n".into();
+    }
+
+    let mut content = String::from("packages:\n");
+    for (name, maps) in packages {
+        content.push_str(&format!("  {}:\n", yaml_scalar(name)));
+        content.push_str("    kind: local\n");
+        if maps.is_empty() {
+            content.push_str("    maps: {}\n");
+            continue;
+        }
+        content.push_str("    maps:\n");
+        for (src, dst) in maps {
+            content.push_str(&yaml_entry(6, src, dst));
+            content.push('\n');
+        }
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::config::PackageType;
+    use crate::test_utils::prelude::*;
+
+    fn packages() -> BTreeMap<String, Vec<(String, String)>> {
+        BTreeMap::from([
+            (
+                "yazi".into(),
+                vec![
+                    ("yazi.toml".into(), "${HOME}/.config/yazi/yazi.toml".into()),
+                    ("init.lua".into(), "${HOME}/.config/yazi/init.lua".into()),
+                ],
+            ),
+            ("nu".into(), vec![]),
+        ])
+    }
+
+    fn check(config: &Config) {
+        expect_eq!(config.packages.len(), 2);
+        expect_eq!(config.packages["nu"].kind, PackageType::Local);
+        expect_that!(config.packages["nu"].maps, is_empty());
+        let maps: Vec<_> = packages()["yazi"]
+            .iter()
+            .map(|(src, dst)| (src.clone(), dst.as_str().into()))
+            .collect();
+        expect_eq!(config.packages["yazi"].maps, maps);
+    }
+
+    #[gtest]
+    fn toml() -> Result<()> {
+        let content = Config::scaffold(Path::new("pkgs.toml"), &packages())?;
+        expect_eq!(
+            content,
+            indoc! {r#"
+                [packages.nu]
+                kind = "local"
+
+                [packages.nu.maps]
+
+                [packages.yazi]
+                kind = "local"
+
+                [packages.yazi.maps]
+                "yazi.toml" = "${HOME}/.config/yazi/yazi.toml"
+                "init.lua" = "${HOME}/.config/yazi/init.lua"
+            "#}
+        );
+        check(&Config::from_toml(&content)?);
+        Ok(())
+    }
+
+    #[gtest]
+    fn yaml() -> Result<()> {
+        let content = Config::scaffold(Path::new("pkgs.yaml"), &packages())?;
+        expect_eq!(
+            content,
+            indoc! {r#"
+                packages:
+                  nu:
+                    kind: local
+                    maps: {}
+                  yazi:
+                    kind: local
+                    maps:
+                      yazi.toml: ${HOME}/.config/yazi/yazi.toml
+                      init.lua: ${HOME}/.config/yazi/init.lua
+            "#}
+        );
+        check(&Config::from_yaml(&content)?);
+        Ok(())
+    }
+
+    #[gtest]
+    fn no_packages() -> Result<()> {
+        let empty = BTreeMap::new();
+
+        let content = Config::scaffold(Path::new("pkgs.toml"), &empty)?;
+        expect_that!(Config::from_toml(&content)?.packages, is_empty());
+
+        let content = Config::scaffold(Path::new("pkgs.yml"), &empty)?;
+        expect_that!(Config::from_yaml(&content)?.packages, is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,277 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use super::{Config, NamedPackage, PkgsParseError};
+use crate::fs::normalize_path;
+
+/// Two packages claiming the same destination, or one inside the other, or two maps of one package
+/// claiming the same destination.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DstConflict {
+    #[error("'{dst}' is mapped by both '{package}' and '{other}'")]
+    Overlap {
+        dst: PathBuf,
+        package: String,
+        other: String,
+    },
+
+    #[error("'{dst}' of '{package}' is inside '{other_dst}' of '{other}'")]
+    Nested {
+        dst: PathBuf,
+        package: String,
+        other_dst: PathBuf,
+        other: String,
+    },
+
+    #[error("'{dst}' is mapped by both '{src}' and '{other_src}' of '{package}'")]
+    Duplicate {
+        dst: PathBuf,
+        package: String,
+        src: String,
+        other_src: String,
+    },
+}
+
+impl DstConflict {
+    /// The package whose destination is reported.
+    pub fn package(&self) -> &str {
+        match self {
+            DstConflict::Overlap { package, .. }
+            | DstConflict::Nested { package, .. }
+            | DstConflict::Duplicate { package, .. } => package,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ValidateError {
+    #[error(transparent)]
+    Parse(#[from] PkgsParseError),
+
+    #[error("conflicting destinations:{}", .0.iter().map(|c| format!("\n  {c}")).collect::<String>())]
+    DstConflicts(Vec<DstConflict>),
+}
+
+impl Config {
+    /// Resolve every package and make sure none of them maps a destination claimed by another one,
+    /// before anything is changed on the filesystem.
+    ///
+    /// Only the packages in `requested` fail on parse errors; other packages that do not resolve
+    /// are left out, like packages skipped by their condition.
+    pub fn validate(&self, requested: &[String]) -> Result<Vec<NamedPackage>, ValidateError> {
+        let mut packages = vec![];
+        for name in self.packages.keys() {
+            match self.get(name) {
+                Ok(package) if package.is_active() => packages.push(package),
+                Ok(_) => {}
+                Err(err) if requested.contains(name) => return Err(err.into()),
+                Err(_) => {}
+            }
+        }
+
+        let conflicts = find_dst_conflicts(&packages);
+        if !conflicts.is_empty() {
+            return Err(ValidateError::DstConflicts(conflicts));
+        }
+        Ok(packages)
+    }
+}
+
+/// Destinations shared by different packages, or sitting inside the linked directory of another.
+/// Within a package, only destinations shared by two maps are reported.
+pub fn find_dst_conflicts(packages: &[NamedPackage]) -> Vec<DstConflict> {
+    let dsts: Vec<(PathBuf, &str, &str)> = packages
+        .iter()
+        .flat_map(|pkg| {
+            pkg.maps().iter().map(|(src, dst)| {
+                let dst = normalize_path(dst).unwrap_or_else(|_| dst.into());
+                (dst, pkg.name(), src.as_str())
+            })
+        })
+        .collect();
+
+    let mut conflicts = vec![];
+    for (i, (dst, package, src)) in dsts.iter().enumerate() {
+        for (other_dst, other, other_src) in &dsts[i + 1..] {
+            let conflict = if package == other {
+                if dst != other_dst {
+                    continue;
+                }
+                DstConflict::Duplicate {
+                    dst: dst.clone(),
+                    package: package.to_string(),
+                    src: src.to_string(),
+                    other_src: other_src.to_string(),
+                }
+            } else if dst == other_dst {
+                DstConflict::Overlap {
+                    dst: dst.clone(),
+                    package: package.to_string(),
+                    other: other.to_string(),
+                }
+            } else if dst.starts_with(other_dst) {
+                DstConflict::Nested {
+                    dst: dst.clone(),
+                    package: package.to_string(),
+                    other_dst: other_dst.clone(),
+                    other: other.to_string(),
+                }
+            } else if other_dst.starts_with(dst) {
+                DstConflict::Nested {
+                    dst: other_dst.clone(),
+                    package: other.to_string(),
+                    other_dst: dst.clone(),
+                    other: package.to_string(),
+                }
+            } else {
+                continue;
+            };
+            conflicts.push(conflict);
+        }
+    }
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::config::Package;
+    use crate::test_utils::prelude::*;
+
+    fn config(packages: &[(&str, &[(&str, &str)])]) -> Config {
+        let packages = packages
+            .iter()
+            .map(|(name, maps)| {
+                let package = Package {
+                    maps: maps
+                        .iter()
+                        .map(|(src, dst)| (src.to_string(), (*dst).into()))
+                        .collect(),
+                    ..Default::default()
+                };
+                (name.to_string(), package)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        Config {
+            packages,
+            ..Default::default()
+        }
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[gtest]
+    fn no_conflict() -> Result<()> {
+        let config = config(&[
+            ("a", &[("file", "/dst/a"), ("dir", "/dst/dir")]),
+            ("b", &[("file", "/dst/b"), ("dir", "/dst/dir-b")]),
+        ]);
+
+        expect_eq!(config.validate(&names(&["a", "b"]))?.len(), 2);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn overlap() {
+        let config = config(&[
+            ("a", &[("file", "/dst/file")]),
+            ("b", &[("f", "/dst/./file")]),
+        ]);
+
+        let err = config.validate(&names(&["a", "b"])).unwrap_err();
+        expect_that!(
+            err,
+            pat!(ValidateError::DstConflicts(elements_are![eq(
+                &DstConflict::Overlap {
+                    dst: "/dst/file".into(),
+                    package: "a".into(),
+                    other: "b".into(),
+                }
+            )]))
+        );
+        expect_eq!(
+            err.to_string(),
+            "conflicting destinations:\n  '/dst/file' is mapped by both 'a' and 'b'"
+        );
+    }
+
+    #[gtest]
+    fn nested() {
+        let config = config(&[
+            ("a", &[("file", "/dst/dir/file")]),
+            ("b", &[("dir", "/dst/dir")]),
+        ]);
+
+        let err = config.validate(&names(&["a", "b"])).unwrap_err();
+        expect_that!(
+            err,
+            pat!(ValidateError::DstConflicts(elements_are![eq(
+                &DstConflict::Nested {
+                    dst: "/dst/dir/file".into(),
+                    package: "a".into(),
+                    other_dst: "/dst/dir".into(),
+                    other: "b".into(),
+                }
+            )]))
+        );
+    }
+
+    #[gtest]
+    fn duplicate_in_same_package() {
+        let config = config(&[("a", &[("f1", "/dst/same"), ("f2", "/dst/./same")])]);
+
+        let err = config.validate(&names(&["a"])).unwrap_err();
+        expect_eq!(
+            err.to_string(),
+            "conflicting destinations:\n  '/dst/same' is mapped by both 'f1' and 'f2' of 'a'"
+        );
+    }
+
+    #[gtest]
+    fn nested_in_same_package_is_not_checked() -> Result<()> {
+        let config = config(&[("a", &[("dir", "/dst/dir"), ("file", "/dst/dir/file")])]);
+
+        expect_eq!(config.validate(&names(&["a", "b"]))?.len(), 1);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn parse_error() {
+        let config = config(&[("a", &[("file", "${UNKNOWN}")])]);
+
+        expect_that!(
+            config.validate(&names(&["a"])).unwrap_err(),
+            pat!(ValidateError::Parse(_))
+        );
+    }
+
+    #[gtest]
+    fn skip_unrequested_parse_error() -> Result<()> {
+        let unrelated = config(&[
+            ("a", &[("file", "/dst/file")]),
+            ("b", &[("file", "${UNKNOWN}")]),
+        ]);
+        expect_eq!(unrelated.validate(&names(&["a"]))?.len(), 1);
+
+        // conflicts with packages that resolve are still reported
+        let config = config(&[
+            ("a", &[("file", "/dst/file")]),
+            ("b", &[("file", "${UNKNOWN}")]),
+            ("c", &[("file", "/dst/file")]),
+        ]);
+        expect_that!(
+            config.validate(&names(&["a"])).unwrap_err(),
+            pat!(ValidateError::DstConflicts(len(eq(1))))
+        );
+
+        Ok(())
+    }
+}
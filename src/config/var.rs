@@ -1,16 +1,54 @@
 use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
 
 use super::{VarsBuildError, VarsParseError};
-use crate::fs::home_dir;
+use crate::fs::{home_dir, host_name, user_name};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct VarMap {
     map: HashMap<String, String>,
 }
 
 impl VarMap {
     fn default_vars() -> HashMap<String, String> {
-        HashMap::from([("HOME".into(), home_dir().to_string_lossy().into())])
+        let home = home_dir();
+        // absolute paths from the environment, as the XDG base directory specification requires
+        let xdg_dir = |var: &str, default: &str| {
+            env::var_os(var)
+                .map(PathBuf::from)
+                .filter(|path| path.is_absolute())
+                .unwrap_or_else(|| home.join(default))
+                .to_string_lossy()
+                .into_owned()
+        };
+
+        let mut vars = HashMap::from([
+            (
+                "XDG_CONFIG_HOME".into(),
+                xdg_dir("XDG_CONFIG_HOME", ".config"),
+            ),
+            (
+                "XDG_DATA_HOME".into(),
+                xdg_dir("XDG_DATA_HOME", ".local/share"),
+            ),
+            (
+                "XDG_STATE_HOME".into(),
+                xdg_dir("XDG_STATE_HOME", ".local/state"),
+            ),
+            ("XDG_CACHE_HOME".into(), xdg_dir("XDG_CACHE_HOME", ".cache")),
+            ("XDG_BIN_HOME".into(), xdg_dir("XDG_BIN_HOME", ".local/bin")),
+            ("HOME".into(), home.to_string_lossy().into_owned()),
+            ("OS".into(), env::consts::OS.into()),
+            ("ARCH".into(), env::consts::ARCH.into()),
+        ]);
+        if let Some(user) = user_name() {
+            vars.insert("USER".into(), user);
+        }
+        if let Some(host) = host_name() {
+            vars.insert("HOSTNAME".into(), host);
+        }
+        vars
     }
 
     pub fn try_new(vars: &[(String, String)]) -> Result<Self, VarsBuildError> {
@@ -37,42 +75,137 @@ impl VarMap {
         Ok(())
     }
 
+    /// Set `var` to `value` as is, without resolving expressions in it.
+    pub(super) fn set(&mut self, var: &str, value: String) {
+        self.map.insert(var.to_string(), value);
+    }
+
     pub fn map(&self) -> &HashMap<String, String> {
         &self.map
     }
 
+    /// Resolve every `${...}` expression in `input`.
+    ///
+    /// An expression is a var name, or `env:` followed by the name of an environment variable,
+    /// optionally followed by `:-` and a default used when the variable is missing or empty. The
+    /// default may contain expressions as well. `$${` is a literal `${`.
     pub fn parse(&self, input: &str) -> Result<String, VarsParseError> {
+        self.parse_at(input, 0)
+    }
+
+    /// [`VarMap::parse`] for `input` found at byte `offset` of the original input, so that errors
+    /// report positions in the original input.
+    fn parse_at(&self, input: &str, offset: usize) -> Result<String, VarsParseError> {
         let mut result = String::with_capacity(input.len());
-        let mut rest = input;
         let mut cursor = 0;
 
-        while let Some(i) = rest.find("${") {
-            result.push_str(&rest[..i]);
+        while let Some(i) = input[cursor..].find('$') {
+            let start = cursor + i;
+            result.push_str(&input[cursor..start]);
+
+            let rest = &input[start..];
+            if rest.starts_with("$${") {
+                result.push_str("${");
+                cursor = start + 3;
+            } else if rest.starts_with("${") {
+                let end = find_closing_brace(input, start)
+                    .ok_or(VarsParseError::UnclosedBrace(offset + start))?;
+                let value = self.expand(&input[start + 2..end], offset + start)?;
+                result.push_str(&value);
+                cursor = end + 1;
+            } else {
+                result.push('$');
+                cursor = start + 1;
+            }
+        }
+        result.push_str(&input[cursor..]);
 
-            let after = i + 2;
-            let Some(end_rel) = rest[after..].find('}') else {
-                return Err(VarsParseError::UnclosedBrace(cursor + i));
-            };
+        Ok(result)
+    }
 
-            let name = &rest[after..after + end_rel];
-            if name.is_empty() {
-                return Err(VarsParseError::EmptyVarName(cursor + i));
-            }
+    /// Resolve the expression `expr` inside the braces of `${...}` found at byte `pos`.
+    fn expand(&self, expr: &str, pos: usize) -> Result<String, VarsParseError> {
+        let (name, default) = match expr.find(":-") {
+            Some(i) => (&expr[..i], Some((&expr[i + 2..], pos + 2 + i + 2))),
+            None => (expr, None),
+        };
 
-            match self.map().get(name) {
-                Some(value) => result.push_str(value),
-                None => return Err(VarsParseError::UnknowndVar(name.to_string(), cursor + i)),
-            }
+        if name.is_empty() || name == "env:" {
+            return Err(VarsParseError::EmptyVarName(pos));
+        }
 
-            rest = &rest[after + end_rel + 1..];
-            cursor += after + end_rel + 1;
+        let (value, err) = match name.strip_prefix("env:") {
+            Some(env_name) => (
+                env::var(env_name).ok(),
+                VarsParseError::UnsetEnvVar(env_name.to_string(), pos),
+            ),
+            None => (
+                self.map.get(name).cloned(),
+                VarsParseError::UnknowndVar(name.to_string(), pos),
+            ),
+        };
+
+        match (value, default) {
+            (Some(value), Some(_)) if !value.is_empty() => Ok(value),
+            (Some(value), None) => Ok(value),
+            (_, Some((default, offset))) => self.parse_at(default, offset),
+            (None, None) => Err(err),
         }
-        result.push_str(rest);
+    }
 
-        Ok(result)
+    /// Replace the longest var value that is a leading part of `path` with a reference to it,
+    /// the reverse of [`VarMap::parse`]. Only absolute values matching whole components are used.
+    pub fn abbreviate(&self, path: &str) -> String {
+        let best = self
+            .map
+            .iter()
+            .filter(|(_, value)| Path::new(value).is_absolute())
+            .map(|(name, value)| (name, value.trim_end_matches('/')))
+            .filter(|(_, value)| !value.is_empty())
+            .filter(|(_, value)| {
+                path.strip_prefix(value)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .max_by(|(n1, v1), (n2, v2)| v1.len().cmp(&v2.len()).then(n2.cmp(n1)));
+
+        match best {
+            Some((name, value)) => format!("${{{name}}}{}", escape(&path[value.len()..])),
+            None => escape(path),
+        }
     }
 }
 
+/// Byte index of the `}` closing the `${` at byte `start` of `input`, skipping nested expressions.
+fn find_closing_brace(input: &str, start: usize) -> Option<usize> {
+    let bytes = input.as_bytes();
+    let mut depth = 0;
+    let mut i = start + 2;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"$${") {
+            i += 3;
+            continue;
+        }
+        if bytes[i..].starts_with(b"${") {
+            depth += 1;
+            i += 2;
+            continue;
+        }
+        if bytes[i] == b'}' {
+            if depth == 0 {
+                return Some(i);
+            }
+            depth -= 1;
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Escape every `${` in `text`, so that [`VarMap::parse`] keeps it as is.
+fn escape(text: &str) -> String {
+    text.replace("${", "$${")
+}
+
 impl Default for VarMap {
     fn default() -> Self {
         Self {
@@ -99,6 +232,37 @@ mod tests {
     fn default_vars() -> Result<()> {
         let var_map = VarMap::try_new(&[])?;
         expect_eq!(var_map.map()["HOME"], home_dir().to_string_lossy());
+        expect_eq!(var_map.map()["OS"], env::consts::OS);
+        expect_eq!(var_map.map()["ARCH"], env::consts::ARCH);
+        for var in [
+            "XDG_CONFIG_HOME",
+            "XDG_DATA_HOME",
+            "XDG_STATE_HOME",
+            "XDG_CACHE_HOME",
+            "XDG_BIN_HOME",
+        ] {
+            expect_true!(Path::new(&var_map.map()[var]).is_absolute());
+        }
+        if env::var_os("XDG_CONFIG_HOME").is_none() {
+            expect_eq!(
+                var_map.map()["XDG_CONFIG_HOME"],
+                home_dir().join(".config").to_string_lossy()
+            );
+        }
+        Ok(())
+    }
+
+    #[gtest]
+    fn override_default_vars() -> Result<()> {
+        let var_map = VarMap::try_new(&[
+            ("XDG_CONFIG_HOME".into(), "${HOME}/config".into()),
+            ("OS".into(), "other".into()),
+        ])?;
+        expect_eq!(
+            var_map.map()["XDG_CONFIG_HOME"],
+            home_dir().join("config").to_string_lossy()
+        );
+        expect_eq!(var_map.map()["OS"], "other");
         Ok(())
     }
 
@@ -207,5 +371,140 @@ mod tests {
             expect_that!(err, pat!(VarsParseError::UnknowndVar("MY_VAR3", &27_usize)));
             Ok(())
         }
+
+        #[gtest]
+        fn env_var() -> Result<()> {
+            let var_map = setup()?;
+            let path = env::var("PATH")?;
+            expect_eq!(var_map.parse("${env:PATH}/bin")?, format!("{path}/bin"));
+
+            let err = var_map
+                .parse("/tmp/${env:PKGS_SURELY_UNSET_VAR}")
+                .unwrap_err();
+            expect_that!(
+                err,
+                pat!(VarsParseError::UnsetEnvVar(
+                    "PKGS_SURELY_UNSET_VAR",
+                    &5_usize
+                ))
+            );
+            expect_eq!(
+                err.to_string(),
+                "unset environment variable 'PKGS_SURELY_UNSET_VAR' found at 5"
+            );
+
+            let err = var_map.parse("${env:}").unwrap_err();
+            expect_that!(err, pat!(VarsParseError::EmptyVarName(&0_usize)));
+            Ok(())
+        }
+
+        #[gtest]
+        fn default_value() -> Result<()> {
+            let var_map = setup()?;
+            expect_eq!(var_map.parse("${MY_VAR1:-other}")?, "hello");
+            expect_eq!(var_map.parse("${MY_VAR3:-other}")?, "other");
+            expect_eq!(var_map.parse("${MY_VAR3:-}")?, "");
+            expect_eq!(
+                var_map.parse("${env:PKGS_SURELY_UNSET_VAR:-${MY_VAR1}/a}/b")?,
+                "hello/a/b"
+            );
+            expect_eq!(
+                var_map.parse("${MY_VAR3:-${MY_VAR4:-${MY_VAR2}}}")?,
+                "hello_world"
+            );
+            // unused defaults are not resolved
+            expect_eq!(var_map.parse("${MY_VAR1:-${MY_VAR3}}")?, "hello");
+            Ok(())
+        }
+
+        #[gtest]
+        fn default_value_error_position() -> Result<()> {
+            let var_map = setup()?;
+            let err = var_map.parse("/tmp/${MY_VAR3:-a/${MY_VAR4}}").unwrap_err();
+            expect_that!(err, pat!(VarsParseError::UnknowndVar("MY_VAR4", &18_usize)));
+
+            let err = var_map.parse("/tmp/${MY_VAR3:-${MY_VAR4}").unwrap_err();
+            expect_that!(err, pat!(VarsParseError::UnclosedBrace(&5_usize)));
+            Ok(())
+        }
+
+        #[gtest]
+        fn escape() -> Result<()> {
+            let var_map = setup()?;
+            expect_eq!(var_map.parse("$${MY_VAR1}")?, "${MY_VAR1}");
+            expect_eq!(var_map.parse("$$ and $x/${MY_VAR1}")?, "$$ and $x/hello");
+            expect_eq!(var_map.parse("${MY_VAR3:-$${}}")?, "${}");
+            Ok(())
+        }
+    }
+
+    mod abbreviate {
+        use super::*;
+
+        fn setup() -> Result<VarMap> {
+            let custom = vec![
+                ("ROOT".into(), "/".into()),
+                ("CONFIG_DIR".into(), "${HOME}/.config".into()),
+                ("NU_DIR".into(), "${CONFIG_DIR}/nushell/".into()),
+                ("SAME_DIR".into(), "${HOME}/.config".into()),
+                ("RELATIVE".into(), "relative".into()),
+            ];
+            Ok(VarMap::try_new(&custom)?)
+        }
+
+        #[gtest]
+        fn use_longest_prefix() -> Result<()> {
+            let var_map = setup()?;
+            let home = home_dir().to_string_lossy().into_owned();
+
+            expect_eq!(
+                var_map.abbreviate(&format!("{home}/.config/nushell/config.nu")),
+                "${NU_DIR}/config.nu"
+            );
+            expect_eq!(
+                var_map.abbreviate(&format!("{home}/.config/yazi")),
+                "${CONFIG_DIR}/yazi"
+            );
+            expect_eq!(
+                var_map.abbreviate(&format!("{home}/.bashrc")),
+                "${HOME}/.bashrc"
+            );
+            expect_eq!(var_map.abbreviate(&home), "${HOME}");
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn match_whole_components() -> Result<()> {
+            let var_map = setup()?;
+            let home = home_dir().to_string_lossy().into_owned();
+
+            expect_eq!(
+                var_map.abbreviate(&format!("{home}/.configs")),
+                "${HOME}/.configs"
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn keep_unmatched() -> Result<()> {
+            let var_map = setup()?;
+            expect_eq!(var_map.abbreviate("/etc/hosts"), "/etc/hosts");
+            expect_eq!(var_map.abbreviate("relative/file"), "relative/file");
+            Ok(())
+        }
+
+        #[gtest]
+        fn escape_literal() -> Result<()> {
+            let var_map = setup()?;
+            let home = home_dir().to_string_lossy().into_owned();
+
+            let path = format!("{home}/${{dir}}");
+            let abbreviated = var_map.abbreviate(&path);
+            expect_eq!(abbreviated, "${HOME}/$${dir}");
+            expect_eq!(var_map.parse(&abbreviated)?, path);
+            Ok(())
+        }
     }
 }
@@ -0,0 +1,179 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Check whether two paths have the same content, comparing directories recursively.
+pub fn same_content<A: AsRef<Path>, B: AsRef<Path>>(a: A, b: B) -> io::Result<bool> {
+    let (a, b) = (a.as_ref(), b.as_ref());
+    let (a_meta, b_meta) = (a.symlink_metadata()?, b.symlink_metadata()?);
+
+    if a_meta.is_symlink() || b_meta.is_symlink() {
+        return Ok(a_meta.is_symlink()
+            && b_meta.is_symlink()
+            && fs::read_link(a)? == fs::read_link(b)?);
+    }
+
+    if a_meta.is_file() && b_meta.is_file() {
+        return Ok(a_meta.len() == b_meta.len() && fs::read(a)? == fs::read(b)?);
+    }
+
+    if a_meta.is_dir() && b_meta.is_dir() {
+        let mut a_entries = fs::read_dir(a)?
+            .map(|e| e.map(|e| e.file_name()))
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut b_entries = fs::read_dir(b)?
+            .map(|e| e.map(|e| e.file_name()))
+            .collect::<io::Result<Vec<_>>>()?;
+        a_entries.sort();
+        b_entries.sort();
+
+        if a_entries != b_entries {
+            return Ok(false);
+        }
+        for name in a_entries {
+            if !same_content(a.join(&name), b.join(&name))? {
+                return Ok(false);
+            }
+        }
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Copy a file, symlink or directory recursively.
+pub fn copy_all<S: AsRef<Path>, D: AsRef<Path>>(src: S, dst: D) -> io::Result<()> {
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+    let meta = src.symlink_metadata()?;
+
+    if meta.is_symlink() {
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(fs::read_link(src)?, dst)?;
+        #[cfg(windows)]
+        fs::copy(src, dst).map(|_| ())?;
+    } else if meta.is_dir() {
+        fs::create_dir(dst)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            copy_all(entry.path(), dst.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(src, dst)?;
+    }
+
+    Ok(())
+}
+
+/// Move a path, falling back to copy and remove when crossing filesystems.
+pub fn move_path<F: AsRef<Path>, T: AsRef<Path>>(from: F, to: T) -> io::Result<()> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    if to.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("Target path '{}' already exists", to.display()),
+        ));
+    }
+
+    match fs::rename(from, to) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            copy_all(from, to)?;
+            remove_all(from)
+        }
+        result => result,
+    }
+}
+
+/// Remove a file, symlink or directory recursively.
+pub fn remove_all<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    if path.symlink_metadata()?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::prelude::*;
+
+    fn setup() -> Result<TempDir> {
+        TempDir::new()?
+            .dir("a/sub")?
+            .file("a/file", "content")?
+            .file("a/sub/file", "sub content")?
+            .dir("b/sub")?
+            .file("b/file", "content")?
+            .file("b/sub/file", "sub content")
+    }
+
+    #[gtest]
+    fn same_dir_content() -> Result<()> {
+        let td = setup()?;
+        expect_true!(same_content(td.join("a"), td.join("b"))?);
+        expect_true!(same_content(td.join("a/file"), td.join("b/file"))?);
+        Ok(())
+    }
+
+    #[gtest]
+    fn different_file_content() -> Result<()> {
+        let td = setup()?.file("b/sub/file", "another content")?;
+        expect_false!(same_content(td.join("a"), td.join("b"))?);
+        expect_false!(same_content(td.join("a/sub/file"), td.join("b/sub/file"))?);
+        Ok(())
+    }
+
+    #[gtest]
+    fn different_entries() -> Result<()> {
+        let td = setup()?.file("b/extra", "")?;
+        expect_false!(same_content(td.join("a"), td.join("b"))?);
+        Ok(())
+    }
+
+    #[gtest]
+    fn file_and_dir() -> Result<()> {
+        let td = setup()?;
+        expect_false!(same_content(td.join("a/file"), td.join("b/sub"))?);
+        Ok(())
+    }
+
+    #[gtest]
+    fn copy_dir() -> Result<()> {
+        let td = setup()?.symlink("a/link", "a/file")?;
+        copy_all(td.join("a"), td.join("c"))?;
+
+        expect_true!(same_content(td.join("a"), td.join("c"))?);
+        expect_that!(td.join("c/link"), is_symlink_for(td.join("a/file")));
+        Ok(())
+    }
+
+    #[gtest]
+    fn move_dir() -> Result<()> {
+        let td = setup()?;
+        move_path(td.join("a"), td.join("c"))?;
+
+        expect_pred!(!td.join("a").exists());
+        expect_true!(same_content(td.join("b"), td.join("c"))?);
+        Ok(())
+    }
+
+    #[gtest]
+    fn move_to_existing_path() -> Result<()> {
+        let td = setup()?;
+        let err = move_path(td.join("a"), td.join("b")).unwrap_err();
+        expect_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        Ok(())
+    }
+
+    #[gtest]
+    fn remove_file_and_dir() -> Result<()> {
+        let td = setup()?;
+        remove_all(td.join("a/file"))?;
+        remove_all(td.join("b"))?;
+
+        expect_pred!(!td.join("a/file").exists());
+        expect_pred!(!td.join("b").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use std::fs;
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Name of the current machine, if it can be found. Looked up once and cached.
+pub fn host_name() -> Option<String> {
+    static HOST_NAME: OnceLock<Option<String>> = OnceLock::new();
+    HOST_NAME.get_or_init(lookup_host_name).clone()
+}
+
+/// Name of the current user, if it can be found in the environment.
+pub fn user_name() -> Option<String> {
+    ["USER", "USERNAME"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|name| !name.is_empty())
+}
+
+fn lookup_host_name() -> Option<String> {
+    #[cfg(windows)]
+    if let Ok(name) = std::env::var("COMPUTERNAME") {
+        return Some(name);
+    }
+
+    let from_file = ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .into_iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .map(|name| name.trim().to_string())
+        .find(|name| !name.is_empty());
+    from_file.or_else(|| {
+        let output = Command::new("hostname").output().ok()?;
+        let name = String::from_utf8(output.stdout).ok()?.trim().to_string();
+        (output.status.success() && !name.is_empty()).then_some(name)
+    })
+}
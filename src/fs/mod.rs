@@ -1,5 +1,11 @@
+mod content;
 mod home;
+mod host;
+mod path;
 mod symlink;
 
+pub use content::{copy_all, move_path, remove_all, same_content};
 pub use home::home_dir;
-pub use symlink::create_symlink;
+pub use host::{host_name, user_name};
+pub use path::{normalize_path, relative_path};
+pub use symlink::{create_relative_symlink, create_symlink, symlink_points_to};
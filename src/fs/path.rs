@@ -0,0 +1,65 @@
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Make `path` absolute and resolve `.` and `..` lexically, without following symlinks.
+pub fn normalize_path(path: impl AsRef<Path>) -> io::Result<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in std::path::absolute(path)?.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    Ok(normalized)
+}
+
+/// Path of `to` relative to the directory `from`, both absolute and normalized.
+pub fn relative_path(from: impl AsRef<Path>, to: impl AsRef<Path>) -> PathBuf {
+    let from: Vec<_> = from.as_ref().components().collect();
+    let to: Vec<_> = to.as_ref().components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push(Component::ParentDir);
+    }
+    for component in &to[common..] {
+        relative.push(component);
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(Component::CurDir);
+    }
+    relative
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::prelude::*;
+
+    #[gtest]
+    fn resolve_dots() -> Result<()> {
+        expect_eq!(normalize_path("/a/./b/../c/")?, PathBuf::from("/a/c"));
+        expect_eq!(normalize_path("/a/../..")?, PathBuf::from("/"));
+        Ok(())
+    }
+
+    #[gtest]
+    fn relative() {
+        expect_eq!(relative_path("/a/b", "/a/b/c"), PathBuf::from("c"));
+        expect_eq!(relative_path("/a/b", "/a/c/d"), PathBuf::from("../c/d"));
+        expect_eq!(relative_path("/a/b/c", "/d"), PathBuf::from("../../../d"));
+        expect_eq!(relative_path("/a/b", "/a/b"), PathBuf::from("."));
+        expect_eq!(relative_path("/a/b", "/a"), PathBuf::from(".."));
+    }
+
+    #[gtest]
+    fn make_absolute() -> Result<()> {
+        let cwd = std::env::current_dir()?;
+        expect_eq!(normalize_path("a/../b")?, cwd.join("b"));
+        Ok(())
+    }
+}
@@ -1,20 +1,40 @@
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use super::{normalize_path, relative_path};
 
 pub fn create_symlink<S: AsRef<Path>, D: AsRef<Path>>(src: S, dst: D) -> io::Result<()> {
-    if !src.as_ref().exists() {
+    let src = canonical_src(src.as_ref())?;
+    symlink(&src, &src, dst.as_ref())
+}
+
+/// Like [`create_symlink`], but the target is written relative to the parent of `dst`, so that
+/// the link survives moving both of them together.
+pub fn create_relative_symlink<S: AsRef<Path>, D: AsRef<Path>>(src: S, dst: D) -> io::Result<()> {
+    let src = canonical_src(src.as_ref())?;
+    let dst = dst.as_ref();
+    let parent = match dst.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.canonicalize()?,
+        _ => std::env::current_dir()?,
+    };
+    symlink(&relative_path(parent, &src), &src, dst)
+}
+
+fn canonical_src(src: &Path) -> io::Result<PathBuf> {
+    if !src.exists() {
         Err(io::Error::new(
             io::ErrorKind::NotFound,
-            format!("Source path '{}' does not exist", src.as_ref().display()),
+            format!("Source path '{}' does not exist", src.display()),
         ))?
     }
+    src.canonicalize()
+}
 
-    let src = src.as_ref().canonicalize().unwrap();
-
+#[cfg_attr(unix, allow(unused_variables))]
+fn symlink(target: &Path, src: &Path, dst: &Path) -> io::Result<()> {
     #[cfg(unix)]
     {
-        use std::os::unix::fs::symlink;
-        symlink(src, dst)
+        std::os::unix::fs::symlink(target, dst)
     }
 
     #[cfg(windows)]
@@ -22,13 +42,34 @@ pub fn create_symlink<S: AsRef<Path>, D: AsRef<Path>>(src: S, dst: D) -> io::Res
         use std::os::windows::fs::{symlink_dir, symlink_file};
 
         if src.is_dir() {
-            symlink_dir(src, dst)
+            symlink_dir(target, dst)
         } else {
-            symlink_file(src, dst)
+            symlink_file(target, dst)
         }
     }
 }
 
+/// Check whether `link` is a symlink resolving to the same path as `src`.
+pub fn symlink_points_to<L: AsRef<Path>, S: AsRef<Path>>(link: L, src: S) -> io::Result<bool> {
+    let link = link.as_ref();
+    let mut target = link.read_link()?;
+    if target.is_relative()
+        && let Some(parent) = link.parent()
+    {
+        target = parent.join(target);
+    }
+
+    let src = src.as_ref();
+    if target == src || normalize_path(&target)? == normalize_path(src)? {
+        return Ok(true);
+    }
+
+    match (target.canonicalize(), src.canonicalize()) {
+        (Ok(target), Ok(src)) => Ok(target == src),
+        _ => Ok(false),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::{self, File};
@@ -70,6 +111,66 @@ mod tests {
         Ok(())
     }
 
+    #[gtest]
+    fn create_relative_symlink_unix() -> Result<()> {
+        let td = TempDir::new()?
+            .dir("a")?
+            .file("a/src_file", "hello")?
+            .dir("b/c")?;
+        let dst = td.join("b/c/dst");
+
+        create_relative_symlink(td.join("a/src_file"), &dst)?;
+        expect_eq!(fs::read_link(&dst)?, PathBuf::from("../../a/src_file"));
+        expect_eq!(fs::read_to_string(&dst)?, "hello");
+        expect_true!(symlink_points_to(&dst, td.join("a/src_file"))?);
+        Ok(())
+    }
+
+    #[gtest]
+    fn symlink_points_to_src() -> Result<()> {
+        let td = TempDir::new()?
+            .dir("src")?
+            .dir("other")?
+            .symlink("dst", "src")?;
+
+        expect_true!(symlink_points_to(td.join("dst"), td.join("src"))?);
+        expect_true!(symlink_points_to(td.join("dst"), td.join("other/../src"))?);
+        expect_false!(symlink_points_to(td.join("dst"), td.join("other"))?);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn symlink_points_to_relative_target() -> Result<()> {
+        let td = TempDir::new()?.dir("a/src")?;
+        std::os::unix::fs::symlink("a/src", td.join("dst"))?;
+
+        expect_true!(symlink_points_to(td.join("dst"), td.join("a/src"))?);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn symlink_points_to_missing_src() -> Result<()> {
+        let td = TempDir::new()?.dir("a")?;
+        std::os::unix::fs::symlink("../src", td.join("a/dst"))?;
+
+        expect_true!(symlink_points_to(td.join("a/dst"), td.join("src"))?);
+        expect_false!(symlink_points_to(td.join("a/dst"), td.join("other"))?);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn symlink_points_to_not_a_symlink() -> Result<()> {
+        let td = TempDir::new()?.dir("src")?.file("dst", "")?;
+
+        let err = symlink_points_to(td.join("dst"), td.join("src")).unwrap_err();
+        expect_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        Ok(())
+    }
+
     #[gtest]
     fn create_symlink_nonexistent_src_unix() -> Result<()> {
         let td = TempDir::new()?;
@@ -0,0 +1,3 @@
+mod repo;
+
+pub use repo::{checkout, clone, fetch, remote_url, resolve};
@@ -0,0 +1,154 @@
+use std::ffi::OsStr;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+fn run<I, S>(repo: Option<&Path>, args: I) -> io::Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut command = Command::new("git");
+    if let Some(repo) = repo {
+        command.arg("-C").arg(repo);
+    }
+    command.args(args);
+
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+pub fn clone(url: &str, path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref().as_os_str();
+    run(
+        None,
+        [
+            OsStr::new("clone"),
+            "--quiet".as_ref(),
+            "--".as_ref(),
+            url.as_ref(),
+            path,
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn fetch(repo: impl AsRef<Path>) -> io::Result<()> {
+    run(
+        Some(repo.as_ref()),
+        ["fetch", "--quiet", "--tags", "--force", "origin"],
+    )?;
+    Ok(())
+}
+
+/// The url the `origin` remote of a repository fetches from.
+pub fn remote_url(repo: impl AsRef<Path>) -> io::Result<String> {
+    run(Some(repo.as_ref()), ["remote", "get-url", "origin"])
+}
+
+/// Resolve a revision to the full hash of the commit it points to.
+pub fn resolve(repo: impl AsRef<Path>, rev: &str) -> io::Result<String> {
+    run(
+        Some(repo.as_ref()),
+        [
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{rev}^{{commit}}"),
+        ],
+    )
+    .map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("revision '{rev}' not found"),
+        )
+    })
+}
+
+pub fn checkout(repo: impl AsRef<Path>, commit: &str) -> io::Result<()> {
+    run(
+        Some(repo.as_ref()),
+        ["checkout", "--quiet", "--detach", commit],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_utils::prelude::*;
+
+    #[gtest]
+    fn clone_and_checkout() -> Result<()> {
+        let td = TempDir::new()?;
+        let remote = git_remote(&td)?;
+        let repo = td.join("clone");
+
+        clone(&remote.to_string_lossy(), &repo)?;
+        expect_eq!(fs::read_to_string(repo.join("src_file"))?, "v2");
+
+        let v1 = resolve(&repo, "refs/tags/v1")?;
+        checkout(&repo, &v1)?;
+        expect_eq!(fs::read_to_string(repo.join("src_file"))?, "v1");
+        expect_eq!(resolve(&repo, "HEAD")?, v1);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn fetch_new_commits() -> Result<()> {
+        let td = TempDir::new()?;
+        let remote = git_remote(&td)?;
+        let repo = td.join("clone");
+        clone(&remote.to_string_lossy(), &repo)?;
+
+        let old = resolve(&repo, "origin/HEAD")?;
+        git_commit(&td, "src_file", "v3")?;
+        fetch(&repo)?;
+
+        expect_ne!(resolve(&repo, "origin/HEAD")?, old);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn origin_url() -> Result<()> {
+        let td = TempDir::new()?;
+        let remote = git_remote(&td)?;
+        let repo = td.join("clone");
+        clone(&remote.to_string_lossy(), &repo)?;
+
+        expect_eq!(remote_url(&repo)?, remote.to_string_lossy());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn clone_invalid_url() -> Result<()> {
+        let td = TempDir::new()?;
+        let err = clone(&td.join("no_such_repo").to_string_lossy(), td.join("clone")).unwrap_err();
+        expect_eq!(err.kind(), io::ErrorKind::Other);
+        Ok(())
+    }
+
+    #[gtest]
+    fn resolve_unknown_revision() -> Result<()> {
+        let td = TempDir::new()?;
+        let remote = git_remote(&td)?;
+        let repo = td.join("clone");
+        clone(&remote.to_string_lossy(), &repo)?;
+
+        let err = resolve(&repo, "no_such_rev").unwrap_err();
+        expect_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 pub mod cli;
 pub mod config;
 pub mod fs;
+pub mod git;
 pub mod logger;
 pub mod meta;
 pub mod runner;
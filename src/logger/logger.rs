@@ -1,6 +1,7 @@
 use std::path::Path;
 
 use super::{LogMessage, LoggerOutput};
+use crate::config::LinkStyle;
 
 pub struct Logger<O: LoggerOutput> {
     output: O,
@@ -32,6 +33,18 @@ impl<O: LoggerOutput> Logger<O> {
         self.log(LogMessage::UnloadModule(module.as_ref().into()));
     }
 
+    pub fn update_module(&mut self, module: impl AsRef<str>) {
+        self.log(LogMessage::UpdateModule(module.as_ref().into()));
+    }
+
+    pub fn add_module(&mut self, module: impl AsRef<str>) {
+        self.log(LogMessage::AddModule(module.as_ref().into()));
+    }
+
+    pub fn relink_module(&mut self, module: impl AsRef<str>) {
+        self.log(LogMessage::RelinkModule(module.as_ref().into()));
+    }
+
     pub fn rollback_load_module(&mut self, module: impl AsRef<str>) {
         self.log(LogMessage::RollbackLoadModule(module.as_ref().into()));
     }
@@ -40,6 +53,18 @@ impl<O: LoggerOutput> Logger<O> {
         self.log(LogMessage::RollbackUnloadModule(module.as_ref().into()));
     }
 
+    pub fn rollback_update_module(&mut self, module: impl AsRef<str>) {
+        self.log(LogMessage::RollbackUpdateModule(module.as_ref().into()));
+    }
+
+    pub fn rollback_add_module(&mut self, module: impl AsRef<str>) {
+        self.log(LogMessage::RollbackAddModule(module.as_ref().into()));
+    }
+
+    pub fn rollback_relink_module(&mut self, module: impl AsRef<str>) {
+        self.log(LogMessage::RollbackRelinkModule(module.as_ref().into()));
+    }
+
     pub fn create_dir(&mut self, path: impl AsRef<Path>) {
         self.log(LogMessage::CreateDir(path.as_ref().into()));
     }
@@ -55,10 +80,85 @@ impl<O: LoggerOutput> Logger<O> {
         self.log(LogMessage::RemoveDir(path.as_ref().into()));
     }
 
-    pub fn remove_symlink(&mut self, src: impl AsRef<Path>, dst: impl AsRef<Path>) {
+    pub fn remove_symlink(
+        &mut self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        link: LinkStyle,
+    ) {
         self.log(LogMessage::RemoveSymlink {
             src: src.as_ref().into(),
             dst: dst.as_ref().into(),
+            link,
+        });
+    }
+
+    pub fn skip_missing_symlink(&mut self, src: impl AsRef<Path>, dst: impl AsRef<Path>) {
+        self.log(LogMessage::SkipMissingSymlink {
+            src: src.as_ref().into(),
+            dst: dst.as_ref().into(),
+        });
+    }
+
+    pub fn move_path(&mut self, from: impl AsRef<Path>, to: impl AsRef<Path>) {
+        self.log(LogMessage::MovePath {
+            from: from.as_ref().into(),
+            to: to.as_ref().into(),
+        });
+    }
+
+    pub fn copy_path(&mut self, src: impl AsRef<Path>, dst: impl AsRef<Path>) {
+        self.log(LogMessage::CopyPath {
+            src: src.as_ref().into(),
+            dst: dst.as_ref().into(),
+        });
+    }
+
+    pub fn remove_copy(&mut self, src: impl AsRef<Path>, dst: impl AsRef<Path>) {
+        self.log(LogMessage::RemoveCopy {
+            src: src.as_ref().into(),
+            dst: dst.as_ref().into(),
+        });
+    }
+
+    pub fn backup_path(&mut self, path: impl AsRef<Path>, backup: impl AsRef<Path>) {
+        self.log(LogMessage::BackupPath {
+            path: path.as_ref().into(),
+            backup: backup.as_ref().into(),
+        });
+    }
+
+    pub fn restore_backup(&mut self, backup: impl AsRef<Path>, path: impl AsRef<Path>) {
+        self.log(LogMessage::RestoreBackup {
+            backup: backup.as_ref().into(),
+            path: path.as_ref().into(),
+        });
+    }
+
+    pub fn clone_repo(&mut self, url: impl AsRef<str>, path: impl AsRef<Path>) {
+        self.log(LogMessage::CloneRepo {
+            url: url.as_ref().into(),
+            path: path.as_ref().into(),
+        });
+    }
+
+    pub fn remove_repo(&mut self, url: impl AsRef<str>, path: impl AsRef<Path>) {
+        self.log(LogMessage::RemoveRepo {
+            url: url.as_ref().into(),
+            path: path.as_ref().into(),
+        });
+    }
+
+    pub fn checkout_repo(
+        &mut self,
+        path: impl AsRef<Path>,
+        from: impl AsRef<str>,
+        to: impl AsRef<str>,
+    ) {
+        self.log(LogMessage::CheckoutRepo {
+            path: path.as_ref().into(),
+            from: from.as_ref().into(),
+            to: to.as_ref().into(),
         });
     }
 }
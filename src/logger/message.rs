@@ -1,16 +1,75 @@
 use std::path::PathBuf;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+use crate::config::LinkStyle;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub enum LogMessage {
     LoadModule(String),
     UnloadModule(String),
+    UpdateModule(String),
+    AddModule(String),
+    RelinkModule(String),
 
     RollbackLoadModule(String),
     RollbackUnloadModule(String),
+    RollbackUpdateModule(String),
+    RollbackAddModule(String),
+    RollbackRelinkModule(String),
 
     CreateDir(PathBuf),
-    CreateSymlink { src: PathBuf, dst: PathBuf },
+    CreateSymlink {
+        src: PathBuf,
+        dst: PathBuf,
+    },
 
     RemoveDir(PathBuf),
-    RemoveSymlink { src: PathBuf, dst: PathBuf },
+    RemoveSymlink {
+        src: PathBuf,
+        dst: PathBuf,
+        /// Style to recreate the symlink with when the removal is undone
+        #[serde(default)]
+        link: LinkStyle,
+    },
+    /// A warning that the symlink was already gone, so there was nothing to remove
+    SkipMissingSymlink {
+        src: PathBuf,
+        dst: PathBuf,
+    },
+
+    MovePath {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    CopyPath {
+        src: PathBuf,
+        dst: PathBuf,
+    },
+    RemoveCopy {
+        src: PathBuf,
+        dst: PathBuf,
+    },
+    BackupPath {
+        path: PathBuf,
+        backup: PathBuf,
+    },
+    RestoreBackup {
+        backup: PathBuf,
+        path: PathBuf,
+    },
+
+    CloneRepo {
+        url: String,
+        path: PathBuf,
+    },
+    RemoveRepo {
+        url: String,
+        path: PathBuf,
+    },
+    CheckoutRepo {
+        path: PathBuf,
+        from: String,
+        to: String,
+    },
 }
@@ -32,16 +32,55 @@ impl<W: Write> LoggerOutput for WriterOutput<W> {
         let message = match message {
             LogMessage::LoadModule(module) => format!("Load Module {module}"),
             LogMessage::UnloadModule(module) => format!("Unload Module {module}"),
+            LogMessage::UpdateModule(module) => format!("Update Module {module}"),
+            LogMessage::AddModule(module) => format!("Add To Module {module}"),
+            LogMessage::RelinkModule(module) => format!("Relink Module {module}"),
             LogMessage::RollbackLoadModule(module) => format!("Rollback Load Module {module}"),
             LogMessage::RollbackUnloadModule(module) => format!("Rollback Unload Module {module}"),
+            LogMessage::RollbackUpdateModule(module) => format!("Rollback Update Module {module}"),
+            LogMessage::RollbackAddModule(module) => format!("Rollback Add To Module {module}"),
+            LogMessage::RollbackRelinkModule(module) => {
+                format!("Rollback Relink Module {module}")
+            }
             LogMessage::CreateDir(path) => format!("Create Directory {}", path.display()),
             LogMessage::CreateSymlink { src, dst } => {
                 format!("Create Symlink {} -> {}", dst.display(), src.display())
             }
             LogMessage::RemoveDir(path) => format!("Remove Directory {}", path.display()),
-            LogMessage::RemoveSymlink { src, dst } => {
+            LogMessage::RemoveSymlink { src, dst, .. } => {
                 format!("Remove Symlink {} -> {}", dst.display(), src.display())
             }
+            LogMessage::SkipMissingSymlink { src, dst } => {
+                format!(
+                    "Warning! Skip Missing Symlink {} -> {}",
+                    dst.display(),
+                    src.display()
+                )
+            }
+            LogMessage::MovePath { from, to } => {
+                format!("Move {} -> {}", from.display(), to.display())
+            }
+            LogMessage::CopyPath { src, dst } => {
+                format!("Copy {} -> {}", src.display(), dst.display())
+            }
+            LogMessage::RemoveCopy { src, dst } => {
+                format!("Remove {} (same as {})", dst.display(), src.display())
+            }
+            LogMessage::BackupPath { path, backup } => {
+                format!("Backup {} -> {}", path.display(), backup.display())
+            }
+            LogMessage::RestoreBackup { backup, path } => {
+                format!("Restore Backup {} -> {}", backup.display(), path.display())
+            }
+            LogMessage::CloneRepo { url, path } => {
+                format!("Clone Repository {url} into {}", path.display())
+            }
+            LogMessage::RemoveRepo { url, path } => {
+                format!("Remove Repository {} cloned from {url}", path.display())
+            }
+            LogMessage::CheckoutRepo { path, from, to } => {
+                format!("Checkout Repository {} {from} -> {to}", path.display())
+            }
         };
 
         // ignore errors on write
@@ -1,53 +1,190 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::io::Stdout;
+use std::path::Path;
+use std::process::ExitCode;
 
 use anyhow::Result;
 use clap::Parser;
 use schemars::schema_for;
 
-use pkgs::cli::{Cli, Command};
-use pkgs::config::Config;
+use pkgs::cli::{Cli, CliError, Command, Summary};
+use pkgs::config::{Config, NamedPackage, Problem, ProblemKind, VarMap};
+use pkgs::fs::{home_dir, host_name};
 use pkgs::logger::WriterOutput;
-use pkgs::meta::TRACE_FILE;
-use pkgs::trace::Trace;
+use pkgs::meta::{TOML_CONFIG_FILE, TRACE_FILE, YAML_CONFIG_FILE};
+use pkgs::runner::{ConflictStrategy, ModuleStatus, RunnerError};
+use pkgs::trace::{Lock, PkgLock, PkgTrace, Trace};
 
 type Runner = pkgs::runner::Runner<WriterOutput<Stdout>>;
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     let cli = Cli::parse();
 
     if let Command::Schema = &cli.command {
         let schema = schema_for!(Config);
         println!("{}", serde_json::to_string_pretty(&schema)?);
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
     };
 
     let cwd = std::env::current_dir()?;
     let stdout = WriterOutput::new(std::io::stdout());
     let runner = Runner::new(&cwd, stdout);
 
-    let config = runner.read_config()?;
+    if let Command::Init { yaml, from_home } = &cli.command {
+        init(*yaml, *from_home, runner)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Command::Recover = &cli.command {
+        recover(runner)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // only look up the hostname when it is needed
+    let host = cli.profile.is_none().then(host_name).flatten();
+
+    if let Command::Check { json } = &cli.command {
+        return check(*json, cli.profile.as_deref(), host.as_deref(), runner);
+    }
+
+    let mut config = runner.read_config()?;
+    // the lock is shared between machines, so it keeps pins of packages a profile disables
+    let declared: BTreeSet<_> = config.packages.keys().cloned().collect();
+    let profile = config.apply_profile(cli.profile.as_deref(), host.as_deref())?;
     let available = config.packages.keys();
 
-    match &cli.command {
-        Command::Load { modules } => load(&config, modules.get(available)?, runner),
-        Command::Unload { modules } => unload(modules.get(available)?, runner),
+    let summary = match &cli.command {
+        Command::Load {
+            modules,
+            dry_run,
+            adopt,
+            backup,
+            force,
+            atomic,
+            fail_fast,
+        } => load(
+            &config,
+            &declared,
+            modules.get(available)?,
+            *atomic,
+            *fail_fast,
+            runner
+                .with_dry_run(*dry_run)
+                .with_force(*force)
+                .with_conflict_strategy(match (adopt, backup) {
+                    (true, _) => ConflictStrategy::Adopt,
+                    (_, true) => ConflictStrategy::Backup,
+                    _ => ConflictStrategy::Fail,
+                }),
+        )?,
+        Command::Unload {
+            modules,
+            dry_run,
+            force,
+            strict,
+            fail_fast,
+        } => {
+            let trace = read_trace(&runner)?;
+            let available: BTreeSet<_> = available.chain(trace.packages.keys()).cloned().collect();
+            unload(
+                trace,
+                modules.get(available)?,
+                *fail_fast,
+                runner
+                    .with_dry_run(*dry_run)
+                    .with_force(*force)
+                    .with_strict(*strict),
+            )?
+        }
+        Command::Prune {
+            dry_run,
+            force,
+            strict,
+            fail_fast,
+        } => {
+            let trace = read_trace(&runner)?;
+            let removed: Vec<_> = trace
+                .packages
+                .keys()
+                .filter(|name| {
+                    !config.packages.contains_key(*name)
+                        || config.get(name).is_ok_and(|pkg| !pkg.is_active())
+                })
+                .cloned()
+                .collect();
+            if removed.is_empty() {
+                println!("Nothing to prune.");
+            }
+            unload(
+                trace,
+                removed,
+                *fail_fast,
+                runner
+                    .with_dry_run(*dry_run)
+                    .with_force(*force)
+                    .with_strict(*strict),
+            )?
+        }
+        Command::Relink { modules, dry_run } => {
+            let trace = read_trace(&runner)?;
+            let loaded: Vec<_> = trace.packages.keys().cloned().collect();
+            relink(trace, modules.get(loaded)?, runner.with_dry_run(*dry_run))?
+        }
+        Command::Update { modules, fail_fast } => update(
+            &config,
+            &declared,
+            modules.get(available)?,
+            *fail_fast,
+            runner,
+        )?,
+        Command::Add { module, path } => {
+            add(&config, module, path, runner)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Command::Status => return status(&config, runner),
         Command::List { oneline } => {
-            println!(
-                "{}",
-                available
-                    .into_iter()
-                    .cloned()
-                    .collect::<Vec<_>>()
-                    .join(if *oneline { "\n" } else { " " })
-            );
-            Ok(())
+            if let Some(profile) = &profile
+                && !oneline
+            {
+                println!("Profile: {profile}");
+            }
+            if *oneline {
+                println!("{}", available.cloned().collect::<Vec<_>>().join("\n"));
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            let (active, skipped): (Vec<_>, Vec<_>) = available
+                .cloned()
+                .partition(|name| config.get(name).is_ok_and(|pkg| pkg.is_active()));
+            println!("{}", active.join(" "));
+            if !skipped.is_empty() {
+                println!("Skipped: {}", skipped.join(" "));
+            }
+            return Ok(ExitCode::SUCCESS);
         }
-        Command::Schema => unreachable!(),
+        Command::Schema | Command::Init { .. } | Command::Recover | Command::Check { .. } => {
+            unreachable!()
+        }
+    };
+
+    if !summary.is_empty() {
+        print!("{summary}");
     }
+    Ok(summary.exit_code())
 }
 
-fn load(config: &Config, modules: Vec<String>, mut runner: Runner) -> Result<()> {
+/// `declared` holds every package of the configuration, including those disabled by the profile.
+fn load(
+    config: &Config,
+    declared: &BTreeSet<String>,
+    modules: Vec<String>,
+    atomic: bool,
+    fail_fast: bool,
+    mut runner: Runner,
+) -> Result<Summary> {
+    config.validate(&modules)?;
     let pkgs_dir = runner.create_pkgs_dir()?;
+    runner.begin_journal()?;
 
     let trace_file = pkgs_dir.join(TRACE_FILE);
     let mut trace = if trace_file.exists() {
@@ -55,30 +192,63 @@ fn load(config: &Config, modules: Vec<String>, mut runner: Runner) -> Result<()>
     } else {
         Trace::default()
     };
+    let mut lock = runner.read_lock()?;
 
+    let action = if runner.is_dry_run() {
+        "Would load"
+    } else {
+        "Loaded"
+    };
+    let mut summary = Summary::new(action).with_dry_run(runner.is_dry_run());
     for name in modules {
         let pkg_trace = trace.packages.get(&name);
-        let package = config.get(&name)?;
+        let mut package = config.get(&name)?;
+        if !package.is_active() {
+            println!("Skipped package: {name}");
+            continue;
+        }
+        if let Some(pkg_lock) = lock.packages.get(&name) {
+            package.lock(pkg_lock);
+        }
 
         match runner.load_module(&package, pkg_trace) {
             Ok(pkg_trace) => {
-                println!("Loaded package: {name}");
+                println!("{action} package: {name}");
+                lock_package(&mut lock, &package, &pkg_trace);
                 trace.packages.insert(name.clone(), pkg_trace);
+                summary.done.push(name);
             }
             Err(e) => {
-                eprintln!("{e}");
-                runner.rollback()?;
+                roll_back_failed(&mut runner, &mut summary, name, e, atomic);
+                if atomic || fail_fast || summary.rollback_failed.is_some() {
+                    break;
+                }
             }
         }
     }
 
-    trace.write_to_file(&trace_file)?;
-
-    Ok(())
+    // `--atomic` leaves the trace as it was
+    let save = !runner.is_dry_run() && (!atomic || summary.failed.is_empty());
+    finish(runner, summary, |runner| {
+        if save {
+            trace.write_to_file(&trace_file)?;
+            lock.packages.retain(|name, _| declared.contains(name));
+            runner.write_lock(&lock)?;
+        }
+        Ok(())
+    })
 }
 
-fn unload(modules: Vec<String>, mut runner: Runner) -> Result<()> {
-    let pkgs_dir = runner.get_pkgs_dir()?;
+fn update(
+    config: &Config,
+    declared: &BTreeSet<String>,
+    modules: Vec<String>,
+    fail_fast: bool,
+    mut runner: Runner,
+) -> Result<Summary> {
+    config.validate(&modules)?;
+    let pkgs_dir = runner.create_pkgs_dir()?;
+    runner.begin_journal()?;
 
     let trace_file = pkgs_dir.join(TRACE_FILE);
     let mut trace = if trace_file.exists() {
@@ -86,6 +256,57 @@ fn unload(modules: Vec<String>, mut runner: Runner) -> Result<()> {
     } else {
         Trace::default()
     };
+    let mut lock = runner.read_lock()?;
+
+    let mut summary = Summary::new("Updated");
+    for name in modules {
+        let pkg_trace = trace.packages.get(&name);
+        let package = config.get(&name)?;
+        if !package.is_active() {
+            println!("Skipped package: {name}");
+            continue;
+        }
+
+        match runner.update_module(&package, pkg_trace) {
+            Ok(pkg_trace) => {
+                println!("Updated package: {name}");
+                lock_package(&mut lock, &package, &pkg_trace);
+                trace.packages.insert(name.clone(), pkg_trace);
+                summary.done.push(name);
+            }
+            Err(e) => {
+                roll_back_failed(&mut runner, &mut summary, name, e, false);
+                if fail_fast || summary.rollback_failed.is_some() {
+                    break;
+                }
+            }
+        }
+    }
+
+    finish(runner, summary, |runner| {
+        trace.write_to_file(&trace_file)?;
+        lock.packages.retain(|name, _| declared.contains(name));
+        runner.write_lock(&lock)?;
+        Ok(())
+    })
+}
+
+fn unload(
+    mut trace: Trace,
+    modules: Vec<String>,
+    fail_fast: bool,
+    mut runner: Runner,
+) -> Result<Summary> {
+    if runner.get_pkgs_dir().is_ok() {
+        runner.begin_journal()?;
+    }
+
+    let action = if runner.is_dry_run() {
+        "Would unload"
+    } else {
+        "Unloaded"
+    };
+    let mut summary = Summary::new(action).with_dry_run(runner.is_dry_run());
 
     for name in modules {
         let Some(pkg_trace) = trace.packages.get(&name) else {
@@ -95,17 +316,308 @@ fn unload(modules: Vec<String>, mut runner: Runner) -> Result<()> {
 
         match runner.unload_module(&name, pkg_trace) {
             Ok(()) => {
-                println!("Unloaded package: {name}");
+                println!("{action} package: {name}");
                 trace.packages.remove(&name);
+                summary.done.push(name);
             }
             Err(e) => {
-                eprintln!("{e}");
-                runner.rollback()?;
+                roll_back_failed(&mut runner, &mut summary, name, e, false);
+                if fail_fast || summary.rollback_failed.is_some() {
+                    break;
+                }
             }
         }
     }
 
+    let save = !runner.is_dry_run() && !summary.done.is_empty();
+    finish(runner, summary, |runner| {
+        if save {
+            trace.write_to_file(&runner.get_pkgs_dir()?.join(TRACE_FILE))?;
+        }
+        Ok(())
+    })
+}
+
+fn relink(trace: Trace, modules: Vec<String>, mut runner: Runner) -> Result<Summary> {
+    if runner.get_pkgs_dir().is_ok() {
+        runner.begin_journal()?;
+    }
+
+    let action = if runner.is_dry_run() {
+        "Would relink"
+    } else {
+        "Relinked"
+    };
+    let mut summary = Summary::new(action).with_dry_run(runner.is_dry_run());
+    for name in modules {
+        let Some(pkg_trace) = trace.packages.get(&name) else {
+            continue;
+        };
+
+        match runner.relink_module(&name, pkg_trace) {
+            Ok(0) => {}
+            Ok(_) => {
+                println!("{action} package: {name}");
+                summary.done.push(name);
+            }
+            Err(e) => {
+                roll_back_failed(&mut runner, &mut summary, name, e, false);
+                if summary.rollback_failed.is_some() {
+                    break;
+                }
+            }
+        }
+    }
+
+    if summary.is_empty() {
+        println!("Nothing to relink.");
+    }
+    finish(runner, summary, |_| Ok(()))
+}
+
+/// Report a failed module and roll it back, or every module handled so far if `atomic`.
+fn roll_back_failed(
+    runner: &mut Runner,
+    summary: &mut Summary,
+    name: String,
+    err: RunnerError,
+    atomic: bool,
+) {
+    eprintln!("{err}");
+    let result = if atomic {
+        runner.rollback_all()
+    } else {
+        runner.rollback()
+    };
+    match result {
+        Ok(()) if atomic => summary.rolled_back.append(&mut summary.done),
+        Ok(()) => {}
+        Err(e) => {
+            eprintln!("{e}");
+            summary.rollback_failed = Some(name.clone());
+        }
+    }
+    summary.failed.push(name);
+}
+
+/// Save the outcome of the run with `save` and commit the journal, unless a rollback failed. The
+/// journal is then left for `pkgs recover`, and the summary exits with the rollback failure status.
+fn finish(
+    mut runner: Runner,
+    summary: Summary,
+    save: impl FnOnce(&mut Runner) -> Result<()>,
+) -> Result<Summary> {
+    if summary.rollback_failed.is_some() {
+        return Ok(summary);
+    }
+
+    save(&mut runner)?;
+    runner.commit_journal()?;
+    Ok(summary)
+}
+
+fn init(yaml: bool, from_home: bool, runner: Runner) -> Result<()> {
+    if let Ok(path) = runner.config_path() {
+        return Err(CliError::ConfigAlreadyExists(path).into());
+    }
+
+    let path = runner.absolute_path_from(if yaml {
+        YAML_CONFIG_FILE
+    } else {
+        TOML_CONFIG_FILE
+    });
+
+    let vars = VarMap::default();
+    let search_dir = home_dir().join(".config");
+    let mut packages = BTreeMap::new();
+    for name in runner.discover_packages()? {
+        let maps = if from_home {
+            runner
+                .propose_maps(&name, &search_dir)?
+                .into_iter()
+                .map(|(src, dst)| (src, vars.abbreviate(&dst.to_string_lossy())))
+                .collect()
+        } else {
+            vec![]
+        };
+        packages.insert(name, maps);
+    }
+
+    std::fs::write(&path, Config::scaffold(&path, &packages)?)?;
+    println!("Created {}", path.display());
+
+    Ok(())
+}
+
+fn add(config: &Config, name: &str, path: &Path, mut runner: Runner) -> Result<()> {
+    let Some(package) = config.packages.get(name) else {
+        return Err(CliError::ModuleNotFound(name.to_string()).into());
+    };
+
+    let dst = pkgs::fs::normalize_path(path)?;
+    let Some(src) = dst.file_name().map(|s| s.to_string_lossy().into_owned()) else {
+        return Err(CliError::InvalidPath(path.to_path_buf()).into());
+    };
+
+    let mut vars = config.with_package_vars(name, VarMap::try_new(&config.vars)?);
+    vars.extends(&package.vars)?;
+    let config_path = match config.package_files.get(name) {
+        Some(path) => path.clone(),
+        None => runner.config_path()?,
+    };
+    let content = Config::add_map(
+        &config_path,
+        name,
+        &src,
+        &vars.abbreviate(&dst.to_string_lossy()),
+    )?;
+
+    let pkgs_dir = runner.create_pkgs_dir()?;
+    runner.begin_journal()?;
+    let trace_file = pkgs_dir.join(TRACE_FILE);
+    let mut trace = if trace_file.exists() {
+        Trace::read_from_file(&trace_file)?
+    } else {
+        Trace::default()
+    };
+
+    let package = config.get(name)?;
+    if let Err(e) = runner.add_to_module(&package, &src, &dst) {
+        runner.rollback()?;
+        runner.commit_journal()?;
+        return Err(e.into());
+    }
+    println!("Added {} to package: {name}", dst.display());
+
+    std::fs::write(&config_path, content)?;
+    trace
+        .packages
+        .entry(name.to_string())
+        .or_insert_with(|| PkgTrace {
+            link: package.link(),
+            ..PkgTrace::new(package.get_directory())
+        })
+        .maps
+        .insert(src, dst.to_string_lossy().into_owned());
     trace.write_to_file(&trace_file)?;
+    runner.commit_journal()?;
 
     Ok(())
 }
+
+fn recover(mut runner: Runner) -> Result<()> {
+    if runner.recover()? {
+        println!("Recovered interrupted operation.");
+    } else {
+        println!("Nothing to recover.");
+    }
+    Ok(())
+}
+
+/// Read the trace file, or an empty trace if nothing has been loaded yet.
+fn read_trace(runner: &Runner) -> Result<Trace> {
+    match runner.get_pkgs_dir() {
+        Ok(pkgs_dir) if pkgs_dir.join(TRACE_FILE).exists() => {
+            Ok(Trace::read_from_file(&pkgs_dir.join(TRACE_FILE))?)
+        }
+        Ok(_) | Err(RunnerError::PkgsDirNotFound) => Ok(Trace::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn status(config: &Config, runner: Runner) -> Result<ExitCode> {
+    let trace = read_trace(&runner)?;
+
+    let names: BTreeSet<_> = config
+        .packages
+        .keys()
+        .chain(trace.packages.keys())
+        .collect();
+
+    let mut attention = false;
+    if runner.has_unfinished_journal() {
+        eprintln!("Warning! {}", RunnerError::UnfinishedJournal);
+        attention = true;
+    }
+
+    for name in names {
+        let package = if config.packages.contains_key(name) {
+            Some(config.get(name)?)
+        } else {
+            None
+        };
+        let status = runner.module_status(package.as_ref(), trace.packages.get(name));
+
+        println!("{name}: {status}");
+        if let ModuleStatus::Broken(broken) = &status {
+            for map in broken {
+                println!("  {map}");
+            }
+        }
+        for src in package.iter().flat_map(NamedPackage::skipped_maps) {
+            println!("  '{src}' skipped");
+        }
+
+        attention |= status.needs_attention();
+    }
+
+    Ok(if attention {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn check(
+    json: bool,
+    profile: Option<&str>,
+    host: Option<&str>,
+    runner: Runner,
+) -> Result<ExitCode> {
+    let problems = match runner.read_config() {
+        Ok(mut config) => match config.apply_profile(profile, host) {
+            Ok(_) => {
+                let (packages, mut problems) = config.check();
+                problems.extend(runner.check_sources(&packages));
+                problems
+            }
+            Err(e) => vec![Problem::new(ProblemKind::InvalidProfile, None, e)],
+        },
+        Err(e) => vec![Problem::new(ProblemKind::InvalidConfig, None, e)],
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&problems)?);
+    } else if problems.is_empty() {
+        println!("No problems found.");
+    } else {
+        for problem in &problems {
+            println!("{problem}");
+        }
+    }
+
+    Ok(if problems.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Record the revision of a git package in the lock, as long as it was checked out from the url
+/// in the configuration. A revision from any other remote could not be checked out again.
+fn lock_package(lock: &mut Lock, package: &NamedPackage, trace: &PkgTrace) {
+    match (package.git(), &trace.revision, &trace.url) {
+        (Some(git), Some(revision), Some(url)) if *url == git.url => {
+            lock.packages.insert(
+                package.name().to_string(),
+                PkgLock {
+                    url: git.url.clone(),
+                    revision: revision.clone(),
+                },
+            );
+        }
+        _ => {
+            lock.packages.remove(package.name());
+        }
+    }
+}
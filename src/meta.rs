@@ -1,6 +1,11 @@
 pub const TOML_CONFIG_FILE: &str = "pkgs.toml";
 pub const YAML_CONFIG_FILE: &str = "pkgs.yaml";
 pub const YML_CONFIG_FILE: &str = "pkgs.yml";
+pub const LOCK_FILE: &str = "pkgs.lock";
 
 pub const PKGS_DIR: &str = ".pkgs";
 pub const TRACE_FILE: &str = "trace.toml";
+pub const JOURNAL_FILE: &str = "journal.jsonl";
+pub const JOURNAL_TRACE_FILE: &str = "journal.trace.toml";
+pub const GIT_DIR: &str = "git";
+pub const BACKUP_SUFFIX: &str = ".pkgs-bak";
@@ -0,0 +1,182 @@
+use std::path::Path;
+
+use super::{AddError, Runner, RunnerError};
+use crate::config::NamedPackage;
+use crate::logger::LoggerOutput;
+
+impl<O: LoggerOutput> Runner<O> {
+    /// Move `dst` into the package as `src` and link it back to where it was.
+    pub fn add_to_module(
+        &mut self,
+        package: &NamedPackage,
+        src: &str,
+        dst: &Path,
+    ) -> Result<(), RunnerError> {
+        self.logger.add_module(package.name());
+        self.link = package.link();
+        self.add_to_module_inner(package, src, dst)
+            .map_err(|e| RunnerError::AddModuleError {
+                source: e,
+                module: package.name().to_string(),
+            })
+    }
+
+    fn add_to_module_inner(
+        &mut self,
+        package: &NamedPackage,
+        src: &str,
+        dst: &Path,
+    ) -> Result<(), AddError> {
+        let pkg_dir = self.absolute_path_from(package.get_directory());
+        if !self.exists(&pkg_dir) {
+            return Err(AddError::PkgDirNotFound(package.name().to_string()));
+        }
+
+        if package.maps().iter().any(|(s, _)| s == src) {
+            return Err(AddError::MapAlreadyExists(src.to_string()));
+        }
+
+        if self.is_symlink(dst) {
+            return Err(AddError::DstIsSymlink(dst.to_path_buf()));
+        }
+        if !self.exists(dst) {
+            return Err(AddError::DstNotFound(dst.to_path_buf()));
+        }
+
+        let src_path = pkg_dir.join(src);
+        if self.exists(&src_path) || self.is_symlink(&src_path) {
+            return Err(AddError::SrcAlreadyExists(src.to_string()));
+        }
+
+        self.move_path(dst, &src_path)?;
+        self.create_symlink(&src_path, dst)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_utils::prelude::*;
+
+    const NEW_FILE_PATH: &str = "./test_c/new_file";
+
+    fn setup() -> Result<(TempDir, NamedPackage, Runner<NullOutput>)> {
+        let (td, pkg, runner) = common_local_pkg()?;
+        let td = td.dir("test_c")?.file(NEW_FILE_PATH, "new content")?;
+        Ok((td, pkg, runner))
+    }
+
+    #[gtest]
+    fn it_works() -> Result<()> {
+        let (td, pkg, mut runner) = setup()?;
+        runner.add_to_module(&pkg, "new_file", &td.join(NEW_FILE_PATH))?;
+
+        let src_path = td.join("test_package/new_file");
+        expect_eq!(fs::read_to_string(&src_path)?, "new content");
+        expect_that!(
+            td.join(NEW_FILE_PATH),
+            is_symlink_for(src_path.canonicalize()?)
+        );
+        expect_eq!(
+            runner.messages().to_vec(),
+            [
+                LogMessage::AddModule("test_package".into()),
+                LogMessage::MovePath {
+                    from: td.join(NEW_FILE_PATH),
+                    to: src_path.clone(),
+                },
+                LogMessage::CreateSymlink {
+                    src: src_path,
+                    dst: td.join(NEW_FILE_PATH),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn add_dir() -> Result<()> {
+        let (td, pkg, mut runner) = setup()?;
+        let td = td
+            .dir("test_d/new_dir")?
+            .file("./test_d/new_dir/inner", "inner content")?;
+        runner.add_to_module(&pkg, "new_dir", &td.join("./test_d/new_dir"))?;
+
+        expect_eq!(
+            fs::read_to_string(td.join("test_package/new_dir/inner"))?,
+            "inner content"
+        );
+        expect_pred!(td.join("./test_d/new_dir").is_symlink());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn dst_not_found() -> Result<()> {
+        let (td, pkg, mut runner) = setup()?;
+        let err = runner
+            .add_to_module(&pkg, "missing", &td.join("missing"))
+            .unwrap_err()
+            .unwrap_add();
+        expect_that!(err, pat!(AddError::DstNotFound(&td.join("missing"))));
+        Ok(())
+    }
+
+    #[gtest]
+    fn dst_is_symlink() -> Result<()> {
+        let (td, pkg, mut runner) = setup()?;
+        let td = td.symlink("./test_c/link", NEW_FILE_PATH)?;
+        let err = runner
+            .add_to_module(&pkg, "link", &td.join("./test_c/link"))
+            .unwrap_err()
+            .unwrap_add();
+        expect_that!(err, pat!(AddError::DstIsSymlink(&td.join("./test_c/link"))));
+        Ok(())
+    }
+
+    #[gtest]
+    fn map_already_exists() -> Result<()> {
+        let (td, pkg, mut runner) = setup()?;
+        let err = runner
+            .add_to_module(&pkg, "src_file", &td.join(NEW_FILE_PATH))
+            .unwrap_err()
+            .unwrap_add();
+        expect_that!(err, pat!(AddError::MapAlreadyExists("src_file")));
+        Ok(())
+    }
+
+    #[gtest]
+    fn src_already_exists() -> Result<()> {
+        let (td, pkg, mut runner) = setup()?;
+        let td = td.file("test_package/new_file", "")?;
+        let err = runner
+            .add_to_module(&pkg, "new_file", &td.join(NEW_FILE_PATH))
+            .unwrap_err()
+            .unwrap_add();
+        expect_that!(err, pat!(AddError::SrcAlreadyExists("new_file")));
+        expect_pred!(!td.join(NEW_FILE_PATH).is_symlink());
+        Ok(())
+    }
+
+    #[gtest]
+    fn rollback() -> Result<()> {
+        let (td, pkg, mut runner) = setup()?;
+        runner.add_to_module(&pkg, "new_file", &td.join(NEW_FILE_PATH))?;
+        runner.rollback()?;
+
+        expect_pred!(!td.join("test_package/new_file").exists());
+        expect_pred!(!td.join(NEW_FILE_PATH).is_symlink());
+        expect_eq!(fs::read_to_string(td.join(NEW_FILE_PATH))?, "new content");
+        expect_that!(
+            runner.messages(),
+            contains(pat!(LogMessage::RollbackAddModule("test_package")))
+        );
+
+        Ok(())
+    }
+}
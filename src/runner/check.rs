@@ -0,0 +1,91 @@
+use super::{LoadError, Runner};
+use crate::config::{NamedPackage, Problem, ProblemKind};
+use crate::logger::LoggerOutput;
+
+impl<O: LoggerOutput> Runner<O> {
+    /// Check that the directory and every source of the packages exist, without changing anything.
+    ///
+    /// Git packages which have not been cloned yet are skipped.
+    pub fn check_sources(&self, packages: &[NamedPackage]) -> Vec<Problem> {
+        let mut problems = vec![];
+        for package in packages {
+            let name = package.name();
+            let pkg_dir = self.absolute_path_from(package.get_directory());
+            if !pkg_dir.exists() {
+                if package.git().is_none() {
+                    problems.push(Problem::new(
+                        ProblemKind::PkgDirNotFound,
+                        Some(name),
+                        LoadError::PkgDirNotFound(name.to_string()),
+                    ));
+                }
+                continue;
+            }
+
+            for (src, _) in package.maps() {
+                if !pkg_dir.join(src).exists() {
+                    problems.push(Problem::new(
+                        ProblemKind::SrcNotExists,
+                        Some(name),
+                        LoadError::SrcNotExists(src.clone()),
+                    ));
+                }
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_utils::prelude::*;
+
+    #[gtest]
+    fn it_works() -> Result<()> {
+        let (td, pkg, _) = common_local_pkg()?;
+        let runner = common_runner(td.path());
+
+        expect_that!(runner.check_sources(&[pkg]), is_empty());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn src_not_exists() -> Result<()> {
+        let (td, pkg, _) = common_local_pkg()?;
+        fs::remove_file(td.join(SRC_FILE_PATH))?;
+        let runner = common_runner(td.path());
+
+        expect_eq!(
+            runner.check_sources(&[pkg]),
+            [Problem::new(
+                ProblemKind::SrcNotExists,
+                Some("test_package"),
+                "source 'src_file' does not exist"
+            )]
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn pkg_dir_not_found() -> Result<()> {
+        let (td, pkg, _) = common_local_pkg()?;
+        fs::remove_dir_all(td.join("test_package"))?;
+        let runner = common_runner(td.path());
+
+        expect_eq!(
+            runner.check_sources(&[pkg]),
+            [Problem::new(
+                ProblemKind::PkgDirNotFound,
+                Some("test_package"),
+                "package directory for 'test_package' not found"
+            )]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,466 @@
+use std::path::{Path, PathBuf};
+
+use super::{IoError, LoadError, Runner};
+use crate::logger::LoggerOutput;
+use crate::meta::BACKUP_SUFFIX;
+use crate::trace::PkgTrace;
+
+/// How to handle a destination that already exists and is not managed by pkgs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// Fail with [`LoadError::DstAlreadyExists`].
+    #[default]
+    Fail,
+
+    /// Move the destination into the package if the source is missing,
+    /// or replace it if it has the same content as the source.
+    Adopt,
+
+    /// Move the destination to `<dst>.pkgs-bak`, restoring it when the package is unloaded.
+    Backup,
+}
+
+impl<O: LoggerOutput> Runner<O> {
+    pub fn with_conflict_strategy(mut self, conflict: ConflictStrategy) -> Self {
+        self.conflict = conflict;
+        self
+    }
+
+    /// Make way for the symlink of `src` if `dst` is an existing file or directory.
+    ///
+    /// Does nothing if `dst` does not exist, is a symlink or the strategy is [`ConflictStrategy::Fail`].
+    /// Returns the backup path if `dst` was backed up.
+    pub(super) fn resolve_conflict(
+        &mut self,
+        src: &str,
+        src_path: &Path,
+        dst_path: &Path,
+    ) -> Result<Option<PathBuf>, LoadError> {
+        if !self.exists(dst_path) || self.is_symlink(dst_path) {
+            return Ok(None);
+        }
+
+        match self.conflict {
+            ConflictStrategy::Fail => Ok(None),
+            ConflictStrategy::Adopt => self.adopt(src, src_path, dst_path).map(|()| None),
+            ConflictStrategy::Backup => self.backup(dst_path).map(Some),
+        }
+    }
+
+    /// Carry the backups of `old_trace` over to `trace`, restoring those whose dst is no longer mapped.
+    pub(super) fn carry_backups(
+        &mut self,
+        old_trace: &PkgTrace,
+        trace: &mut PkgTrace,
+    ) -> Result<(), LoadError> {
+        for (dst, backup) in &old_trace.backups {
+            if trace.backups.contains_key(dst) {
+                continue;
+            }
+
+            let dst_path = PathBuf::from(dst);
+            if trace.maps.values().all(|d| d != dst) && !self.exists(&dst_path) {
+                self.restore_backup(backup, dst_path)?;
+                continue;
+            }
+
+            trace.backups.insert(dst.clone(), backup.clone());
+        }
+
+        Ok(())
+    }
+
+    fn backup(&mut self, dst_path: &Path) -> Result<PathBuf, LoadError> {
+        let mut backup = dst_path.as_os_str().to_owned();
+        backup.push(BACKUP_SUFFIX);
+        let backup = PathBuf::from(backup);
+
+        if self.exists(&backup) || self.is_symlink(&backup) {
+            return Err(LoadError::BackupAlreadyExists {
+                dst: dst_path.to_path_buf(),
+                backup,
+            });
+        }
+
+        self.backup_path(dst_path, &backup)?;
+        Ok(backup)
+    }
+
+    fn adopt(&mut self, src: &str, src_path: &Path, dst_path: &Path) -> Result<(), LoadError> {
+        if !self.exists(src_path) {
+            if let Some(parent) = src_path.parent() {
+                self.create_dirs(parent)?;
+            }
+            self.move_path(dst_path, src_path)?;
+            return Ok(());
+        }
+
+        let same = crate::fs::same_content(src_path, dst_path).map_err(|source| IoError {
+            source,
+            action: format!(
+                "compare '{}' with '{}'",
+                dst_path.display(),
+                src_path.display()
+            ),
+        })?;
+        if !same {
+            return Err(LoadError::AdoptContentMismatch {
+                src: src.to_string(),
+                dst: dst_path.to_path_buf(),
+            });
+        }
+
+        self.remove_copy(src_path, dst_path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    use super::*;
+    use crate::config::NamedPackage;
+    use crate::logger::NullOutput;
+    use crate::test_utils::prelude::*;
+
+    fn adopt_runner(td: &TempDir) -> Runner<NullOutput> {
+        common_runner(td.path()).with_conflict_strategy(ConflictStrategy::Adopt)
+    }
+
+    fn setup() -> Result<(TempDir, NamedPackage)> {
+        let (td, pkg, _) = common_local_pkg()?;
+        let td = td.dir("test_pkg")?.dir(DST_DIR_PATH)?;
+        Ok((td, pkg))
+    }
+
+    #[gtest]
+    fn fail_by_default() -> Result<()> {
+        let (td, pkg) = setup()?;
+
+        let err = common_runner(td.path())
+            .load_module(&pkg, None)
+            .unwrap_err()
+            .unwrap_load();
+        expect_that!(err, pat!(LoadError::DstAlreadyExists { .. }));
+
+        Ok(())
+    }
+
+    mod adopt {
+        use super::*;
+
+        #[gtest]
+        fn move_into_package() -> Result<()> {
+            let (td, pkg) = setup()?;
+            fs::remove_file(td.join(SRC_FILE_PATH))?;
+            let td = td.file(DST_FILE_PATH, "existing content")?;
+
+            let mut runner = adopt_runner(&td);
+            runner.load_module(&pkg, None)?;
+
+            expect_eq!(
+                fs::read_to_string(td.join(SRC_FILE_PATH))?,
+                "existing content"
+            );
+            expect_that!(
+                td.join(DST_FILE_PATH),
+                is_symlink_for(td.join(SRC_FILE_PATH).canonicalize()?)
+            );
+            expect_that!(
+                runner.messages(),
+                contains(pat!(LogMessage::MovePath {
+                    from: &td.join(DST_FILE_PATH),
+                    to: &td.join(SRC_FILE_PATH),
+                }))
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn move_into_missing_parent() -> Result<()> {
+            let (td, mut pkg) = setup()?;
+            let td = td.file("existing_file", "")?;
+            pkg.insert_map("sub/new_file", td.join("existing_file").to_string_lossy());
+
+            let mut runner = adopt_runner(&td);
+            runner.load_module(&pkg, None)?;
+
+            expect_pred!(td.join("test_package/sub/new_file").is_file());
+            expect_that!(
+                runner.messages(),
+                contains(pat!(LogMessage::CreateDir(&td.join("test_package/sub"))))
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn replace_same_content() -> Result<()> {
+            let (td, pkg) = setup()?;
+            let td = td.file(DST_FILE_PATH, "test_content")?;
+
+            let mut runner = adopt_runner(&td);
+            runner.load_module(&pkg, None)?;
+
+            expect_that!(
+                td.join(DST_FILE_PATH),
+                is_symlink_for(td.join(SRC_FILE_PATH).canonicalize()?)
+            );
+            expect_that!(
+                td.join(DST_DIR_PATH),
+                is_symlink_for(td.join(SRC_DIR_PATH).canonicalize()?)
+            );
+            expect_that!(
+                runner.messages(),
+                superset_of([
+                    &LogMessage::RemoveCopy {
+                        src: td.join(SRC_FILE_PATH),
+                        dst: td.join(DST_FILE_PATH),
+                    },
+                    &LogMessage::RemoveCopy {
+                        src: td.join(SRC_DIR_PATH),
+                        dst: td.join(DST_DIR_PATH),
+                    },
+                ])
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn content_mismatch() -> Result<()> {
+            let (td, pkg) = setup()?;
+            let td = td.file(DST_FILE_PATH, "another content")?;
+
+            let err = adopt_runner(&td)
+                .load_module(&pkg, None)
+                .unwrap_err()
+                .unwrap_load();
+            expect_that!(
+                err,
+                pat!(LoadError::AdoptContentMismatch {
+                    src: "src_file",
+                    dst: &td.join(DST_FILE_PATH)
+                })
+            );
+            expect_eq!(
+                fs::read_to_string(td.join(DST_FILE_PATH))?,
+                "another content"
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn rollback() -> Result<()> {
+            let (td, pkg) = setup()?;
+            fs::remove_file(td.join(SRC_FILE_PATH))?;
+            let td = td.file(DST_FILE_PATH, "existing content")?;
+
+            let mut runner = adopt_runner(&td);
+            runner.load_module(&pkg, None)?;
+            runner.rollback()?;
+
+            expect_pred!(!td.join(SRC_FILE_PATH).exists());
+            expect_pred!(td.join(DST_FILE_PATH).is_file());
+            expect_eq!(
+                fs::read_to_string(td.join(DST_FILE_PATH))?,
+                "existing content"
+            );
+            expect_pred!(td.join(DST_DIR_PATH).is_dir());
+            expect_pred!(!td.join(DST_DIR_PATH).is_symlink());
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn dry_run() -> Result<()> {
+            let (td, pkg) = setup()?;
+            fs::remove_file(td.join(SRC_FILE_PATH))?;
+            let td = td.file(DST_FILE_PATH, "existing content")?;
+
+            let mut dry = adopt_runner(&td).with_dry_run(true);
+            dry.load_module(&pkg, None)?;
+
+            expect_pred!(!td.join(SRC_FILE_PATH).exists());
+            expect_pred!(td.join(DST_FILE_PATH).is_file());
+
+            let mut runner = adopt_runner(&td);
+            runner.load_module(&pkg, None)?;
+            expect_eq!(dry.messages(), runner.messages());
+
+            Ok(())
+        }
+    }
+
+    mod backup {
+        use super::*;
+
+        fn backup_runner(td: &TempDir) -> Runner<NullOutput> {
+            common_runner(td.path()).with_conflict_strategy(ConflictStrategy::Backup)
+        }
+
+        fn backup_of(td: &TempDir, path: &str) -> PathBuf {
+            PathBuf::from(format!("{}{BACKUP_SUFFIX}", td.join(path).display()))
+        }
+
+        #[gtest]
+        fn it_works() -> Result<()> {
+            let (td, pkg) = setup()?;
+            let td = td.file(DST_FILE_PATH, "original content")?;
+
+            let mut runner = backup_runner(&td);
+            let trace = runner.load_module(&pkg, None)?;
+
+            expect_that!(
+                td.join(DST_FILE_PATH),
+                is_symlink_for(td.join(SRC_FILE_PATH).canonicalize()?)
+            );
+            expect_eq!(
+                fs::read_to_string(backup_of(&td, DST_FILE_PATH))?,
+                "original content"
+            );
+            expect_pred!(backup_of(&td, DST_DIR_PATH).is_dir());
+            expect_eq!(
+                trace.backups,
+                BTreeMap::from([
+                    (
+                        td.join(DST_FILE_PATH).to_string_lossy().into_owned(),
+                        backup_of(&td, DST_FILE_PATH).to_string_lossy().into_owned(),
+                    ),
+                    (
+                        td.join(DST_DIR_PATH).to_string_lossy().into_owned(),
+                        backup_of(&td, DST_DIR_PATH).to_string_lossy().into_owned(),
+                    ),
+                ])
+            );
+            expect_that!(
+                runner.messages(),
+                contains(pat!(LogMessage::BackupPath {
+                    path: &td.join(DST_FILE_PATH),
+                    backup: &backup_of(&td, DST_FILE_PATH),
+                }))
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn restore_on_unload() -> Result<()> {
+            let (td, pkg) = setup()?;
+            let td = td.file(DST_FILE_PATH, "original content")?;
+
+            let trace = backup_runner(&td).load_module(&pkg, None)?;
+            let mut runner = common_runner(td.path());
+            runner.unload_module("test_package", &trace)?;
+
+            expect_pred!(!td.join(DST_FILE_PATH).is_symlink());
+            expect_eq!(
+                fs::read_to_string(td.join(DST_FILE_PATH))?,
+                "original content"
+            );
+            expect_pred!(!td.join(DST_DIR_PATH).is_symlink());
+            expect_pred!(td.join(DST_DIR_PATH).is_dir());
+            expect_pred!(!backup_of(&td, DST_FILE_PATH).exists());
+            expect_pred!(!backup_of(&td, DST_DIR_PATH).exists());
+            expect_that!(
+                runner.messages(),
+                contains(pat!(LogMessage::RestoreBackup {
+                    backup: &backup_of(&td, DST_FILE_PATH),
+                    path: &td.join(DST_FILE_PATH),
+                }))
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn backup_already_exists() -> Result<()> {
+            let (td, pkg) = setup()?;
+            let td = td
+                .file(DST_FILE_PATH, "original content")?
+                .file(format!("{DST_FILE_PATH}{BACKUP_SUFFIX}"), "old backup")?;
+
+            let err = backup_runner(&td)
+                .load_module(&pkg, None)
+                .unwrap_err()
+                .unwrap_load();
+            expect_that!(
+                err,
+                pat!(LoadError::BackupAlreadyExists {
+                    dst: &td.join(DST_FILE_PATH),
+                    backup: &backup_of(&td, DST_FILE_PATH),
+                })
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn reload() -> Result<()> {
+            let (td, mut pkg) = setup()?;
+            let td = td.file(DST_FILE_PATH, "original content")?;
+
+            let trace = backup_runner(&td).load_module(&pkg, None)?;
+
+            pkg.remove_map("src_file");
+            let new_trace = backup_runner(&td).load_module(&pkg, Some(&trace))?;
+
+            expect_eq!(
+                fs::read_to_string(td.join(DST_FILE_PATH))?,
+                "original content"
+            );
+            expect_pred!(!backup_of(&td, DST_FILE_PATH).exists());
+            expect_eq!(
+                new_trace.backups,
+                BTreeMap::from([(
+                    td.join(DST_DIR_PATH).to_string_lossy().into_owned(),
+                    backup_of(&td, DST_DIR_PATH).to_string_lossy().into_owned(),
+                )])
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn rollback() -> Result<()> {
+            let (td, pkg) = setup()?;
+            let td = td.file(DST_FILE_PATH, "original content")?;
+
+            let mut runner = backup_runner(&td);
+            runner.load_module(&pkg, None)?;
+            runner.rollback()?;
+
+            expect_eq!(
+                fs::read_to_string(td.join(DST_FILE_PATH))?,
+                "original content"
+            );
+            expect_pred!(!td.join(DST_DIR_PATH).is_symlink());
+            expect_pred!(!backup_of(&td, DST_FILE_PATH).exists());
+            expect_pred!(!backup_of(&td, DST_DIR_PATH).exists());
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn dry_run() -> Result<()> {
+            let (td, pkg) = setup()?;
+            let td = td.file(DST_FILE_PATH, "original content")?;
+
+            let mut dry = backup_runner(&td).with_dry_run(true);
+            dry.load_module(&pkg, None)?;
+
+            expect_pred!(!td.join(DST_FILE_PATH).is_symlink());
+            expect_pred!(!backup_of(&td, DST_FILE_PATH).exists());
+
+            let mut runner = backup_runner(&td);
+            runner.load_module(&pkg, None)?;
+            expect_eq!(dry.messages(), runner.messages());
+
+            Ok(())
+        }
+    }
+}
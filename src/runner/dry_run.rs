@@ -0,0 +1,378 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::Runner;
+use crate::fs::symlink_points_to;
+use crate::logger::LoggerOutput;
+
+/// Filesystem changes that would have been made by a dry run.
+///
+/// Queries fall back to the real filesystem for paths the dry run has not touched.
+#[derive(Debug, Default)]
+pub(super) struct DryRun {
+    files: HashSet<PathBuf>,
+    dirs: HashSet<PathBuf>,
+    symlinks: HashSet<PathBuf>,
+    repos: HashSet<PathBuf>,
+    removed: HashSet<PathBuf>,
+}
+
+impl DryRun {
+    fn exists(&self, path: &Path) -> bool {
+        if self.removed.contains(path) {
+            return false;
+        }
+        if self.files.contains(path) || self.dirs.contains(path) || self.symlinks.contains(path) {
+            return true;
+        }
+        // the content of a repository is unknown until it is really cloned
+        if self.repos.iter().any(|repo| path.starts_with(repo)) {
+            return true;
+        }
+        path.exists()
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        if self.removed.contains(path) || self.files.contains(path) || self.dirs.contains(path) {
+            return false;
+        }
+        self.symlinks.contains(path) || path.is_symlink()
+    }
+
+    fn points_to(&self, path: &Path, src: &Path) -> bool {
+        // symlinks created by the dry run always point to their source
+        self.symlinks.contains(path) || symlink_points_to(path, src).unwrap_or(false)
+    }
+
+    pub(super) fn is_empty_dir(&self, path: &Path) -> bool {
+        let in_overlay = self
+            .files
+            .iter()
+            .chain(&self.dirs)
+            .chain(&self.symlinks)
+            .any(|p| p.parent() == Some(path));
+        if in_overlay {
+            return false;
+        }
+
+        match fs::read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(Result::ok)
+                .all(|entry| self.removed.contains(&entry.path())),
+            Err(_) => self.dirs.contains(path),
+        }
+    }
+
+    pub(super) fn is_cloned(&self, path: &Path) -> bool {
+        self.repos.contains(path)
+    }
+
+    pub(super) fn create_dir(&mut self, path: &Path) -> io::Result<()> {
+        for ancestor in path.ancestors() {
+            if self.exists(ancestor) {
+                break;
+            }
+            self.removed.remove(ancestor);
+            self.dirs.insert(ancestor.to_path_buf());
+        }
+        Ok(())
+    }
+
+    pub(super) fn create_symlink(&mut self, src: &Path, dst: &Path) -> io::Result<()> {
+        if !self.exists(src) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Source path '{}' does not exist", src.display()),
+            ));
+        }
+        if self.exists(dst) || self.is_symlink(dst) {
+            return Err(io::ErrorKind::AlreadyExists.into());
+        }
+
+        self.removed.remove(dst);
+        self.symlinks.insert(dst.to_path_buf());
+        Ok(())
+    }
+
+    pub(super) fn remove_dir(&mut self, path: &Path) -> io::Result<()> {
+        if !self.exists(path) || self.is_symlink(path) {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        self.dirs.remove(path);
+        self.removed.insert(path.to_path_buf());
+        Ok(())
+    }
+
+    pub(super) fn remove_symlink(&mut self, dst: &Path) -> io::Result<()> {
+        if !self.is_symlink(dst) {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        self.symlinks.remove(dst);
+        self.removed.insert(dst.to_path_buf());
+        Ok(())
+    }
+
+    pub(super) fn move_path(&mut self, from: &Path, to: &Path) -> io::Result<()> {
+        self.copy_path(from, to)?;
+        self.remove_path(from)
+    }
+
+    pub(super) fn copy_path(&mut self, src: &Path, dst: &Path) -> io::Result<()> {
+        if !self.exists(src) {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+        if self.exists(dst) || self.is_symlink(dst) {
+            return Err(io::ErrorKind::AlreadyExists.into());
+        }
+
+        self.removed.remove(dst);
+        self.files.insert(dst.to_path_buf());
+        Ok(())
+    }
+
+    pub(super) fn remove_path(&mut self, path: &Path) -> io::Result<()> {
+        if !self.exists(path) && !self.is_symlink(path) {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        self.files.remove(path);
+        self.dirs.remove(path);
+        self.symlinks.remove(path);
+        self.removed.insert(path.to_path_buf());
+        Ok(())
+    }
+
+    pub(super) fn clone_repo(&mut self, path: &Path) -> io::Result<()> {
+        if self.exists(path) {
+            return Err(io::ErrorKind::AlreadyExists.into());
+        }
+
+        self.removed.remove(path);
+        self.repos.insert(path.to_path_buf());
+        Ok(())
+    }
+
+    pub(super) fn remove_repo(&mut self, path: &Path) -> io::Result<()> {
+        if !self.exists(path) {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+
+        self.repos.remove(path);
+        self.removed.insert(path.to_path_buf());
+        Ok(())
+    }
+}
+
+impl<O: LoggerOutput> Runner<O> {
+    /// Record the operations in the log without touching the filesystem.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run.then(DryRun::default);
+        self
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.is_some()
+    }
+
+    pub(super) fn exists(&self, path: impl AsRef<Path>) -> bool {
+        match &self.dry_run {
+            Some(dry_run) => dry_run.exists(path.as_ref()),
+            None => path.as_ref().exists(),
+        }
+    }
+
+    pub(super) fn is_symlink(&self, path: impl AsRef<Path>) -> bool {
+        match &self.dry_run {
+            Some(dry_run) => dry_run.is_symlink(path.as_ref()),
+            None => path.as_ref().is_symlink(),
+        }
+    }
+
+    /// Whether the symlink `path` resolves to `src`.
+    pub(super) fn points_to(&self, path: impl AsRef<Path>, src: impl AsRef<Path>) -> bool {
+        match &self.dry_run {
+            Some(dry_run) => dry_run.points_to(path.as_ref(), src.as_ref()),
+            None => symlink_points_to(path, src).unwrap_or(false),
+        }
+    }
+
+    /// Whether the symlink `path` points somewhere other than `src` and must be left alone,
+    /// because the runner is not forced.
+    pub(super) fn is_foreign(&self, path: impl AsRef<Path>, src: impl AsRef<Path>) -> bool {
+        !self.force && !self.points_to(path, src)
+    }
+
+    pub(super) fn is_empty_dir(&self, path: impl AsRef<Path>) -> bool {
+        match &self.dry_run {
+            Some(dry_run) => dry_run.is_empty_dir(path.as_ref()),
+            None => fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::logger::NullOutput;
+    use crate::test_utils::prelude::*;
+
+    fn dry_runner(td: &TempDir) -> Runner<NullOutput> {
+        common_runner(td.path()).with_dry_run(true)
+    }
+
+    #[gtest]
+    fn load_directly() -> Result<()> {
+        let (td, pkg, mut runner) = common_local_pkg()?;
+
+        let mut dry = dry_runner(&td);
+        let dry_trace = dry.load_module(&pkg, None)?;
+
+        expect_pred!(!td.join("test_pkg").exists());
+        expect_pred!(!td.join("test_a").exists());
+
+        let trace = runner.load_module(&pkg, None)?;
+        expect_eq!(dry_trace, trace);
+        expect_eq!(dry.messages(), runner.messages());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn load_with_trace() -> Result<()> {
+        let (td, mut pkg, mut runner) = common_local_pkg()?;
+        let trace = runner.load_module(&pkg, None)?;
+        pkg.remove_map("src_file");
+        pkg.insert_map(
+            "src_file",
+            td.join("new_dir/new_dst_file").to_string_lossy(),
+        );
+
+        let mut dry = dry_runner(&td);
+        dry.load_module(&pkg, Some(&trace))?;
+
+        expect_that!(
+            td.join(DST_FILE_PATH),
+            is_symlink_for(td.join(SRC_FILE_PATH).canonicalize()?)
+        );
+        expect_pred!(!td.join("new_dir").exists());
+
+        let mut runner = common_runner(td.path());
+        runner.load_module(&pkg, Some(&trace))?;
+        expect_eq!(dry.messages(), runner.messages());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn create_shared_parent_once() -> Result<()> {
+        let (td, mut pkg, mut runner) = common_local_pkg()?;
+        let td = td.file("test_package/another_file", "")?;
+        pkg.insert_map(
+            "another_file",
+            td.join("test_pkg/another_file").to_string_lossy(),
+        );
+
+        let mut dry = dry_runner(&td);
+        dry.load_module(&pkg, None)?;
+
+        let create_dirs = dry
+            .messages()
+            .iter()
+            .filter(|msg| **msg == LogMessage::CreateDir(td.join("./test_pkg")))
+            .count();
+        expect_eq!(create_dirs, 1);
+
+        runner.load_module(&pkg, None)?;
+        expect_eq!(dry.messages(), runner.messages());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn detect_errors() -> Result<()> {
+        let (td, pkg, _) = common_local_pkg()?;
+        fs::create_dir_all(td.join(DST_FILE_PATH))?;
+
+        let mut dry = dry_runner(&td);
+        let err = dry.load_module(&pkg, None).unwrap_err().unwrap_load();
+        expect_that!(
+            err,
+            pat!(LoadError::DstAlreadyExists {
+                src: "src_file",
+                dst: &td.join(DST_FILE_PATH)
+            })
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn rollback() -> Result<()> {
+        let (td, pkg, _) = common_local_pkg()?;
+
+        let mut dry = dry_runner(&td);
+        dry.load_module(&pkg, None)?;
+        let rollback_begin = dry.messages().len();
+        dry.rollback()?;
+
+        expect_that!(
+            dry.messages()[rollback_begin],
+            pat!(LogMessage::RollbackLoadModule("test_package"))
+        );
+        expect_eq!(dry.messages().len(), rollback_begin * 2);
+        expect_pred!(!td.join("test_pkg").exists());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn unload() -> Result<()> {
+        let (td, pkg, mut runner) = common_local_pkg()?;
+        let trace = runner.load_module(&pkg, None)?;
+
+        let mut dry = dry_runner(&td);
+        dry.unload_module("test_package", &trace)?;
+
+        expect_pred!(td.join(DST_FILE_PATH).is_symlink());
+        expect_pred!(td.join(DST_DIR_PATH).is_symlink());
+
+        let mut runner = common_runner(td.path());
+        runner.unload_module("test_package", &trace)?;
+        expect_eq!(dry.messages(), runner.messages());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn git_package_not_cloned() -> Result<()> {
+        let (td, pkg, _) = common_git_pkg(Package::default())?;
+
+        let mut dry = dry_runner(&td);
+        let trace = dry.load_module(&pkg, None)?;
+
+        expect_eq!(trace.directory, GIT_PKG_DIR);
+        expect_pred!(!td.join(GIT_PKG_DIR).exists());
+        expect_pred!(!td.join(DST_FILE_PATH).exists());
+        expect_that!(
+            dry.messages(),
+            superset_of([
+                &LogMessage::CloneRepo {
+                    url: td.join("remote.git").to_string_lossy().into_owned(),
+                    path: td.join(GIT_PKG_DIR),
+                },
+                &LogMessage::CreateSymlink {
+                    src: td.join(GIT_PKG_DIR).join("src_file"),
+                    dst: td.join(DST_FILE_PATH),
+                },
+            ])
+        );
+
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use std::path::PathBuf;
 use thiserror::Error;
 
 use crate::config::ConfigError;
+use crate::trace::TraceIoError;
 
 #[derive(Debug, Error)]
 pub enum RunnerError {
@@ -13,6 +14,9 @@ pub enum RunnerError {
     #[error(transparent)]
     ConfigReadError(#[from] ConfigError),
 
+    #[error("Fail to access lock file: {0}")]
+    LockFileError(#[from] TraceIoError),
+
     #[error("'.pkgs' directory already exists but is not a directory.")]
     PkgsDirNotADir,
 
@@ -28,8 +32,23 @@ pub enum RunnerError {
     #[error("Fail to unload {module}: {source}")]
     UnloadModuleError { source: UnloadError, module: String },
 
+    #[error("Fail to update {module}: {source}")]
+    UpdateModuleError { source: LoadError, module: String },
+
+    #[error("Fail to add to {module}: {source}")]
+    AddModuleError { source: AddError, module: String },
+
+    #[error("Fail to relink {module}: {source}")]
+    RelinkModuleError { source: IoError, module: String },
+
     #[error("No action to rollback")]
     NoActionToRollback,
+
+    #[error("Fail to access journal: {0}")]
+    JournalError(TraceIoError),
+
+    #[error("An interrupted operation was found, run `pkgs recover` to undo it.")]
+    UnfinishedJournal,
 }
 
 #[derive(Debug, Error)]
@@ -55,6 +74,24 @@ pub enum LoadError {
 
     #[error("'{dst}' for '{src}' found in trace file but not a symlink")]
     DstNotSymlink { src: String, dst: PathBuf },
+
+    #[error(
+        "'{dst}' for '{src}' points somewhere else, use `--force` to replace it, or `pkgs relink` if the package has moved"
+    )]
+    DstPointsElsewhere { src: String, dst: PathBuf },
+
+    #[error("'{dst}' differs from '{src}' and could not be adopted")]
+    AdoptContentMismatch { src: String, dst: PathBuf },
+
+    #[error("backup '{backup}' for '{dst}' already exists")]
+    BackupAlreadyExists { dst: PathBuf, backup: PathBuf },
+
+    #[error("'{repo}' was cloned from '{origin}' instead of '{url}', remove it to clone again")]
+    RepoUrlChanged {
+        repo: PathBuf,
+        url: String,
+        origin: String,
+    },
 }
 
 #[derive(Debug, Error)]
@@ -67,12 +104,37 @@ pub enum UnloadError {
 
     #[error("'{dst}' for '{src}' found in trace file but not a symlink")]
     DstNotSymlink { src: String, dst: PathBuf },
+
+    #[error("'{dst}' for '{src}' points somewhere else, use `--force` to remove it")]
+    DstPointsElsewhere { src: String, dst: PathBuf },
+}
+
+#[derive(Debug, Error)]
+pub enum AddError {
+    #[error(transparent)]
+    Io(#[from] IoError),
+
+    #[error("package directory for '{0}' not found")]
+    PkgDirNotFound(String),
+
+    #[error("'{0}' does not exist")]
+    DstNotFound(PathBuf),
+
+    #[error("'{0}' is a symlink and could not be added")]
+    DstIsSymlink(PathBuf),
+
+    #[error("'{0}' is already mapped in the package")]
+    MapAlreadyExists(String),
+
+    #[error("'{0}' already exists in the package directory")]
+    SrcAlreadyExists(String),
 }
 
 impl RunnerError {
     pub fn unwrap_load(self) -> LoadError {
         match self {
-            RunnerError::LoadModuleError { source, .. } => source,
+            RunnerError::LoadModuleError { source, .. }
+            | RunnerError::UpdateModuleError { source, .. } => source,
             _ => panic!("Called unwrap_load on a non-LoadModuleError variant"),
         }
     }
@@ -83,4 +145,11 @@ impl RunnerError {
             _ => panic!("Called unwrap_unload on a non-UnloadModuleError variant"),
         }
     }
+
+    pub fn unwrap_add(self) -> AddError {
+        match self {
+            RunnerError::AddModuleError { source, .. } => source,
+            _ => panic!("Called unwrap_add on a non-AddModuleError variant"),
+        }
+    }
 }
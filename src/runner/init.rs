@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{IoError, Runner};
+use crate::logger::LoggerOutput;
+
+/// How deep to look for matching files when proposing maps.
+const SEARCH_DEPTH: usize = 4;
+
+impl<O: LoggerOutput> Runner<O> {
+    /// Names of all visible subdirectories of the working directory, sorted.
+    pub fn discover_packages(&self) -> Result<Vec<String>, IoError> {
+        let entries = fs::read_dir(&self.cwd).map_err(|source| IoError {
+            source,
+            action: format!("read directory '{}'", self.cwd.display()),
+        })?;
+
+        let mut packages: Vec<String> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        packages.sort();
+
+        Ok(packages)
+    }
+
+    /// Propose maps for the entries of `package` whose names match files under `search_dir`.
+    ///
+    /// When several files match, the one with the package name in its path wins, then the
+    /// shallowest one.
+    pub fn propose_maps(
+        &self,
+        package: &str,
+        search_dir: &Path,
+    ) -> Result<Vec<(String, PathBuf)>, IoError> {
+        let pkg_dir = self.absolute_path_from(package);
+        let entries = fs::read_dir(&pkg_dir).map_err(|source| IoError {
+            source,
+            action: format!("read directory '{}'", pkg_dir.display()),
+        })?;
+
+        let mut names: Vec<OsString> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name())
+            .filter(|name| name != ".git")
+            .collect();
+        names.sort();
+
+        let mut found = HashMap::new();
+        self.search(search_dir, &names, SEARCH_DEPTH, &mut found);
+
+        let maps = names
+            .into_iter()
+            .filter_map(|name| {
+                let candidates = found.remove(&name)?;
+                let best = candidates.into_iter().min_by_key(|path: &PathBuf| {
+                    let rel = path.strip_prefix(search_dir).unwrap_or(path);
+                    let in_package = rel.iter().any(|c| c == package);
+                    (!in_package, rel.components().count(), path.clone())
+                })?;
+                Some((name.to_string_lossy().into_owned(), best))
+            })
+            .collect();
+
+        Ok(maps)
+    }
+
+    fn search(
+        &self,
+        dir: &Path,
+        names: &[OsString],
+        depth: usize,
+        found: &mut HashMap<OsString, Vec<PathBuf>>,
+    ) {
+        if depth == 0 || dir.starts_with(&self.cwd) {
+            return;
+        }
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            let name = entry.file_name();
+
+            if names.contains(&name) {
+                found.entry(name).or_default().push(path);
+                continue;
+            }
+
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                self.search(&path, names, depth - 1, found);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::prelude::*;
+
+    fn setup() -> Result<TempDir> {
+        let td = TempDir::new()?
+            .dir("repo/yazi/plugins")?
+            .file("repo/yazi/yazi.toml", "")?
+            .file("repo/yazi/init.lua", "")?
+            .file("repo/yazi/theme.toml", "")?
+            .dir("repo/nu")?
+            .file("repo/nu/config.nu", "")?
+            .dir("repo/.git")?
+            .file("repo/README.md", "")?
+            .dir("config/yazi/plugins")?
+            .file("config/yazi/yazi.toml", "")?
+            .file("config/init.lua", "")?
+            .file("config/yazi/init.lua", "")?
+            .dir("config/other/nested/deeper/deepest")?
+            .file("config/other/nested/deeper/deepest/theme.toml", "")?
+            .dir("config/nushell")?
+            .file("config/nushell/config.nu", "")?;
+        Ok(td)
+    }
+
+    #[gtest]
+    fn discover_packages() -> Result<()> {
+        let td = setup()?;
+        let runner = common_runner(td.join("repo"));
+        expect_eq!(runner.discover_packages()?, ["nu", "yazi"]);
+        Ok(())
+    }
+
+    #[gtest]
+    fn propose_maps() -> Result<()> {
+        let td = setup()?;
+        let runner = common_runner(td.join("repo"));
+
+        expect_eq!(
+            runner.propose_maps("yazi", &td.join("config"))?,
+            [
+                ("init.lua".to_string(), td.join("config/yazi/init.lua")),
+                ("plugins".to_string(), td.join("config/yazi/plugins")),
+                ("yazi.toml".to_string(), td.join("config/yazi/yazi.toml")),
+            ]
+        );
+        expect_eq!(
+            runner.propose_maps("nu", &td.join("config"))?,
+            [("config.nu".to_string(), td.join("config/nushell/config.nu"))]
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn skip_working_directory() -> Result<()> {
+        let td = setup()?;
+        let runner = common_runner(td.join("repo"));
+        expect_that!(runner.propose_maps("yazi", &td.join("repo"))?, is_empty());
+        Ok(())
+    }
+
+    #[gtest]
+    fn missing_search_dir() -> Result<()> {
+        let td = setup()?;
+        let runner = common_runner(td.join("repo"));
+        expect_that!(
+            runner.propose_maps("yazi", &td.join("missing"))?,
+            is_empty()
+        );
+        Ok(())
+    }
+}
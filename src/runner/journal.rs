@@ -0,0 +1,257 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use super::{Runner, RunnerError};
+use crate::logger::LoggerOutput;
+use crate::meta::{JOURNAL_FILE, JOURNAL_TRACE_FILE, PKGS_DIR, TRACE_FILE};
+use crate::trace::Journal;
+
+fn journal_error(err: io::Error) -> RunnerError {
+    RunnerError::JournalError(err.into())
+}
+
+impl<O: LoggerOutput> Runner<O> {
+    fn journal_path(&self) -> PathBuf {
+        self.cwd.join(PKGS_DIR).join(JOURNAL_FILE)
+    }
+
+    pub fn has_unfinished_journal(&self) -> bool {
+        self.journal_path().exists()
+    }
+
+    /// Start recording the actions of an operation, keeping a copy of the trace file to restore if
+    /// the operation is interrupted. Nothing is recorded for a dry run.
+    pub fn begin_journal(&mut self) -> Result<(), RunnerError> {
+        if self.has_unfinished_journal() {
+            return Err(RunnerError::UnfinishedJournal);
+        }
+        if self.is_dry_run() {
+            return Ok(());
+        }
+
+        let pkgs_dir = self.get_pkgs_dir()?;
+        let trace_file = pkgs_dir.join(TRACE_FILE);
+        let saved_trace = pkgs_dir.join(JOURNAL_TRACE_FILE);
+        if trace_file.exists() {
+            fs::copy(&trace_file, &saved_trace).map_err(journal_error)?;
+        } else if saved_trace.exists() {
+            fs::remove_file(&saved_trace).map_err(journal_error)?;
+        }
+
+        let journal = Journal::create(&self.journal_path()).map_err(RunnerError::JournalError)?;
+        self.journal = Some(journal);
+        Ok(())
+    }
+
+    /// Finish the operation, to be called once the trace file has been written.
+    pub fn commit_journal(&mut self) -> Result<(), RunnerError> {
+        if self.journal.take().is_none() {
+            return Ok(());
+        }
+
+        fs::remove_file(self.journal_path()).map_err(journal_error)?;
+        let saved_trace = self.cwd.join(PKGS_DIR).join(JOURNAL_TRACE_FILE);
+        if saved_trace.exists() {
+            fs::remove_file(saved_trace).map_err(journal_error)?;
+        }
+        Ok(())
+    }
+
+    /// Undo the actions of an interrupted operation, last first, and restore the trace file it
+    /// started with. Returns whether there was anything to recover.
+    pub fn recover(&mut self) -> Result<bool, RunnerError> {
+        let path = self.journal_path();
+        if !path.exists() {
+            return Ok(false);
+        }
+
+        let entries = Journal::read(&path).map_err(RunnerError::JournalError)?;
+        let mut journal = Journal::open(&path).map_err(RunnerError::JournalError)?;
+        for (i, entry) in entries.iter().enumerate().rev() {
+            match self.undo(&entry.action) {
+                Ok(()) => {}
+                // the last action may not have been performed before the interruption, or may
+                // have been undone already by a recovery which was interrupted in turn
+                Err(_) if i + 1 == entries.len() => {}
+                Err(e) => return Err(e.into()),
+            }
+            journal.truncate(entry.offset).map_err(journal_error)?;
+        }
+        drop(journal);
+
+        let pkgs_dir = self.cwd.join(PKGS_DIR);
+        let trace_file = pkgs_dir.join(TRACE_FILE);
+        let saved_trace = pkgs_dir.join(JOURNAL_TRACE_FILE);
+        if saved_trace.exists() {
+            fs::rename(&saved_trace, &trace_file).map_err(journal_error)?;
+        } else if trace_file.exists() {
+            fs::remove_file(&trace_file).map_err(journal_error)?;
+        }
+        fs::remove_file(&path).map_err(journal_error)?;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::config::NamedPackage;
+    use crate::test_utils::prelude::*;
+    use crate::trace::Trace;
+
+    fn setup() -> Result<(TempDir, NamedPackage, Runner<NullOutput>)> {
+        let (td, pkg, mut runner) = common_local_pkg()?;
+        runner.create_pkgs_dir()?;
+        Ok((td, pkg, runner))
+    }
+
+    #[gtest]
+    fn nothing_to_recover() -> Result<()> {
+        let (_td, _pkg, mut runner) = setup()?;
+        expect_that!(runner.recover()?, eq(false));
+        Ok(())
+    }
+
+    #[gtest]
+    fn commit() -> Result<()> {
+        let (td, pkg, mut runner) = setup()?;
+        runner.begin_journal()?;
+        runner.load_module(&pkg, None)?;
+        expect_pred!(runner.has_unfinished_journal());
+
+        runner.commit_journal()?;
+        expect_pred!(!runner.has_unfinished_journal());
+        expect_pred!(!td.join(PKGS_DIR).join(JOURNAL_TRACE_FILE).exists());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn begin_with_unfinished_journal() -> Result<()> {
+        let (_td, _pkg, mut runner) = setup()?;
+        runner.begin_journal()?;
+
+        let mut runner = common_runner(&runner.cwd);
+        let err = runner.begin_journal().unwrap_err();
+        expect_that!(err, pat!(RunnerError::UnfinishedJournal));
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn skip_dry_run() -> Result<()> {
+        let (_td, pkg, runner) = setup()?;
+        let mut runner = runner.with_dry_run(true);
+        runner.begin_journal()?;
+        runner.load_module(&pkg, None)?;
+
+        expect_pred!(!runner.has_unfinished_journal());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn recover_interrupted_load() -> Result<()> {
+        let (td, pkg, mut runner) = setup()?;
+        runner.begin_journal()?;
+        runner.load_module(&pkg, None)?;
+        drop(runner);
+
+        let mut runner = common_runner(td.path());
+        expect_that!(runner.recover()?, eq(true));
+
+        expect_pred!(!td.join(DST_FILE_PATH).exists());
+        expect_pred!(!td.join(DST_DIR_PATH).exists());
+        expect_pred!(!td.join("test_a").exists());
+        expect_pred!(!runner.has_unfinished_journal());
+        expect_pred!(!td.join(PKGS_DIR).join(TRACE_FILE).exists());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn restore_trace() -> Result<()> {
+        let (td, pkg, mut runner) = setup()?;
+        let trace = Trace {
+            packages: [("test_package".into(), runner.load_module(&pkg, None)?)].into(),
+        };
+        let trace_file = td.join(PKGS_DIR).join(TRACE_FILE);
+        trace.write_to_file(&trace_file)?;
+
+        let mut runner = common_runner(td.path());
+        runner.begin_journal()?;
+        runner.unload_module("test_package", &trace.packages["test_package"])?;
+        Trace::default().write_to_file(&trace_file)?;
+        drop(runner);
+
+        let mut runner = common_runner(td.path());
+        runner.recover()?;
+
+        expect_that!(
+            td.join(DST_FILE_PATH),
+            is_symlink_for(td.join(SRC_FILE_PATH))
+        );
+        expect_eq!(Trace::read_from_file(&trace_file)?, trace);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn skip_failed_action() -> Result<()> {
+        let (td, pkg, mut runner) = setup()?;
+        fs::write(td.join("test_a"), "not a directory")?;
+        runner.begin_journal()?;
+        expect_pred!(runner.load_module(&pkg, None).is_err());
+        runner.rollback()?;
+        drop(runner);
+
+        let mut runner = common_runner(td.path());
+        expect_that!(runner.recover()?, eq(true));
+
+        expect_pred!(!td.join(DST_FILE_PATH).exists());
+        expect_pred!(td.join("test_a").is_file());
+        expect_pred!(!runner.has_unfinished_journal());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn recover_relative_symlinks() -> Result<()> {
+        let (td, pkg, mut runner) = setup()?;
+        let trace = runner.load_module(&relative_pkg(&pkg)?, None)?;
+
+        let mut runner = common_runner(td.path());
+        runner.begin_journal()?;
+        runner.unload_module("test_package", &trace)?;
+        drop(runner);
+
+        let mut runner = common_runner(td.path());
+        runner.recover()?;
+
+        expect_pred!(fs::read_link(td.join(DST_FILE_PATH))?.is_relative());
+        expect_pred!(fs::read_link(td.join(DST_DIR_PATH))?.is_relative());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn skip_action_not_performed() -> Result<()> {
+        let (td, pkg, mut runner) = setup()?;
+        runner.begin_journal()?;
+        runner.load_module(&pkg, None)?;
+        fs::remove_file(td.join(DST_DIR_PATH))?;
+        drop(runner);
+
+        let mut runner = common_runner(td.path());
+        runner.recover()?;
+
+        expect_pred!(!td.join(DST_FILE_PATH).exists());
+        expect_pred!(!runner.has_unfinished_journal());
+
+        Ok(())
+    }
+}
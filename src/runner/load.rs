@@ -1,6 +1,6 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use super::{LoadError, Runner, RunnerError};
+use super::{IoError, LoadError, Runner, RunnerError};
 use crate::config::NamedPackage;
 use crate::logger::LoggerOutput;
 use crate::trace::PkgTrace;
@@ -12,46 +12,114 @@ impl<O: LoggerOutput> Runner<O> {
         trace: Option<&PkgTrace>,
     ) -> Result<PkgTrace, RunnerError> {
         self.logger.load_module(package.name());
+        self.load_module_inner(package, trace)
+            .map_err(|e| RunnerError::LoadModuleError {
+                source: e,
+                module: package.name().to_string(),
+            })
+    }
+
+    pub(super) fn load_module_inner(
+        &mut self,
+        package: &NamedPackage,
+        trace: Option<&PkgTrace>,
+    ) -> Result<PkgTrace, LoadError> {
+        self.link = package.link();
+        self.prepare_source(package)?;
 
-        let result = if let Some(trace) = trace {
-            self.load_with_trace(package, trace)
+        let mut new_trace = if let Some(trace) = trace {
+            self.load_with_trace(package, trace)?
         } else {
-            self.load_directly(package)
+            self.load_directly(package)?
+        };
+
+        if package.git().is_some() && !self.is_dry_run() {
+            let repo = self.absolute_path_from(package.get_directory());
+            let revision = crate::git::resolve(&repo, "HEAD").map_err(|source| IoError {
+                source,
+                action: format!("resolve HEAD of '{}'", repo.display()),
+            })?;
+            let url = crate::git::remote_url(&repo).map_err(|source| IoError {
+                source,
+                action: format!("read the remote url of '{}'", repo.display()),
+            })?;
+            new_trace.revision = Some(revision);
+            new_trace.url = Some(url);
+        }
+
+        Ok(new_trace)
+    }
+
+    fn prepare_source(&mut self, package: &NamedPackage) -> Result<(), LoadError> {
+        let Some(git) = package.git() else {
+            return Ok(());
         };
 
-        result.map_err(|e| RunnerError::LoadModuleError {
-            source: e,
-            module: package.name().to_string(),
-        })
+        let repo = self.absolute_path_from(package.get_directory());
+        if !self.exists(&repo) {
+            self.clone_repo(&git.url, &repo)?;
+            self.checkout_repo(&repo, &git.target())?;
+            return Ok(());
+        }
+
+        self.check_origin(&repo, &git.url)?;
+        if let Some(locked) = &git.locked {
+            if crate::git::resolve(&repo, locked).is_err() {
+                self.fetch_repo(&repo)?;
+            }
+            self.checkout_repo(&repo, locked)?;
+        }
+
+        Ok(())
+    }
+
+    /// Make sure the existing clone at `repo` fetches from `url`, which may have been changed in
+    /// the configuration since it was cloned.
+    pub(super) fn check_origin(&self, repo: &Path, url: &str) -> Result<(), LoadError> {
+        let origin = crate::git::remote_url(repo).map_err(|source| IoError {
+            source,
+            action: format!("read the remote url of '{}'", repo.display()),
+        })?;
+        if origin != url {
+            return Err(LoadError::RepoUrlChanged {
+                repo: repo.to_path_buf(),
+                url: url.to_string(),
+                origin,
+            });
+        }
+        Ok(())
     }
 
     fn load_directly(&mut self, package: &NamedPackage) -> Result<PkgTrace, LoadError> {
         let mut trace = PkgTrace::new(package.get_directory());
+        trace.link = package.link();
 
         let pkg_dir = self.absolute_path_from(&trace.directory);
-        if !pkg_dir.exists() {
+        if !self.exists(&pkg_dir) {
             return Err(LoadError::PkgDirNotFound(package.name().to_string()));
         }
 
         for (src, dst) in package.maps() {
             let src_path = pkg_dir.join(src);
-            if !src_path.exists() {
+            let dst_path = PathBuf::from(&dst);
+            if let Some(backup) = self.resolve_conflict(src, &src_path, &dst_path)? {
+                trace
+                    .backups
+                    .insert(dst.clone(), backup.to_string_lossy().into_owned());
+            }
+
+            if !self.exists(&src_path) {
                 return Err(LoadError::SrcNotExists(src.to_string()));
             }
 
-            let dst_path = PathBuf::from(&dst);
-            if dst_path.exists() {
+            if self.exists(&dst_path) {
                 return Err(LoadError::DstAlreadyExists {
                     src: src.clone(),
                     dst: dst_path,
                 });
             }
 
-            if let Some(parent) = dst_path.parent()
-                && !parent.exists()
-            {
-                self.create_dir(parent)?;
-            }
+            self.create_parent_dirs(&dst_path, &mut trace)?;
 
             self.create_symlink(&src_path, &dst_path)?;
 
@@ -67,36 +135,50 @@ impl<O: LoggerOutput> Runner<O> {
         old_trace: &PkgTrace,
     ) -> Result<PkgTrace, LoadError> {
         let directory = package.get_directory();
-        if directory != old_trace.directory {
+        if directory != old_trace.directory || package.link() != old_trace.link {
             return self.load_with_pkg_dir_changed(package, old_trace);
         }
 
         let mut trace = PkgTrace::new(directory);
+        trace.link = package.link();
 
         let pkg_dir = self.absolute_path_from(&trace.directory);
-        if !pkg_dir.exists() {
+        if !self.exists(&pkg_dir) {
             return Err(LoadError::PkgDirNotFound(package.name().to_string()));
         }
 
         for (src, dst) in package.maps() {
             let src_path = pkg_dir.join(src);
-            if !src_path.exists() {
-                return Err(LoadError::SrcNotExists(src.to_string()));
+            let dst_path = PathBuf::from(&dst);
+            if let Some(backup) = self.resolve_conflict(src, &src_path, &dst_path)? {
+                trace
+                    .backups
+                    .insert(dst.clone(), backup.to_string_lossy().into_owned());
             }
 
-            let dst_path = PathBuf::from(&dst);
+            if !self.exists(&src_path) {
+                return Err(LoadError::SrcNotExists(src.to_string()));
+            }
 
             if let Some(dst_in_trace) = old_trace.maps.get(src) {
                 let dst_in_trace = PathBuf::from(dst_in_trace);
-                if dst_in_trace.exists() {
-                    if !dst_in_trace.is_symlink() {
+                // a dangling symlink does not exist, but still has to be checked and removed
+                if self.exists(&dst_in_trace) || self.is_symlink(&dst_in_trace) {
+                    if !self.is_symlink(&dst_in_trace) {
                         return Err(LoadError::DstNotSymlink {
                             src: src.clone(),
                             dst: dst_in_trace,
                         });
                     }
 
-                    if dst_path == dst_in_trace {
+                    if self.is_foreign(&dst_in_trace, &src_path) {
+                        return Err(LoadError::DstPointsElsewhere {
+                            src: src.clone(),
+                            dst: dst_in_trace,
+                        });
+                    }
+
+                    if dst_path == dst_in_trace && self.points_to(&dst_path, &src_path) {
                         trace.maps.insert(src.into(), dst.into());
                         continue;
                     }
@@ -105,18 +187,14 @@ impl<O: LoggerOutput> Runner<O> {
                 }
             }
 
-            if dst_path.exists() {
+            if self.exists(&dst_path) {
                 return Err(LoadError::DstAlreadyExists {
                     src: src.clone(),
                     dst: dst_path,
                 });
             }
 
-            if let Some(parent) = dst_path.parent()
-                && !parent.exists()
-            {
-                self.create_dir(parent)?;
-            }
+            self.create_parent_dirs(&dst_path, &mut trace)?;
 
             self.create_symlink(&src_path, dst)?;
 
@@ -126,26 +204,91 @@ impl<O: LoggerOutput> Runner<O> {
         for (src, dst) in &old_trace.maps {
             let dst_path = PathBuf::from(&dst);
 
-            if dst_path.exists() && !trace.maps.contains_key(src) {
-                if !dst_path.is_symlink() {
+            if (self.exists(&dst_path) || self.is_symlink(&dst_path))
+                && !trace.maps.contains_key(src)
+            {
+                if !self.is_symlink(&dst_path) {
                     return Err(LoadError::DstNotSymlink {
                         src: src.clone(),
                         dst: dst_path,
                     });
                 }
-                self.remove_symlink(pkg_dir.join(src), dst)?;
+                let src_path = pkg_dir.join(src);
+                if self.is_foreign(&dst_path, &src_path) {
+                    return Err(LoadError::DstPointsElsewhere {
+                        src: src.clone(),
+                        dst: dst_path,
+                    });
+                }
+                self.remove_symlink(src_path, dst)?;
             }
         }
 
+        self.carry_backups(old_trace, &mut trace)?;
+        self.carry_dirs(old_trace, &mut trace)?;
+
         Ok(trace)
     }
 
     fn load_with_pkg_dir_changed(
         &mut self,
-        _package: &NamedPackage,
-        _old_trace: &PkgTrace,
+        package: &NamedPackage,
+        old_trace: &PkgTrace,
     ) -> Result<PkgTrace, LoadError> {
-        todo!()
+        let pkg_dir = self.absolute_path_from(package.get_directory());
+        if !self.exists(&pkg_dir) {
+            return Err(LoadError::PkgDirNotFound(package.name().to_string()));
+        }
+
+        let old_pkg_dir = self.absolute_path_from(&old_trace.directory);
+        // the old symlinks are recorded with their own style, to recreate them as they were
+        self.link = old_trace.link;
+        for (src, dst) in &old_trace.maps {
+            let dst_path = PathBuf::from(&dst);
+            // the old symlinks dangle if the old package directory is gone
+            if !self.exists(&dst_path) && !self.is_symlink(&dst_path) {
+                continue;
+            }
+
+            if !self.is_symlink(&dst_path) {
+                return Err(LoadError::DstNotSymlink {
+                    src: src.clone(),
+                    dst: dst_path,
+                });
+            }
+            let src_path = old_pkg_dir.join(src);
+            if self.is_foreign(&dst_path, &src_path) {
+                return Err(LoadError::DstPointsElsewhere {
+                    src: src.clone(),
+                    dst: dst_path,
+                });
+            }
+            self.remove_symlink(src_path, dst_path)?;
+        }
+        self.link = package.link();
+
+        let mut trace = self.load_directly(package)?;
+        self.carry_backups(old_trace, &mut trace)?;
+        self.carry_dirs(old_trace, &mut trace)?;
+
+        Ok(trace)
+    }
+
+    /// Create the missing parent directories of `dst_path` and record them in `trace`.
+    fn create_parent_dirs(&mut self, dst_path: &Path, trace: &mut PkgTrace) -> Result<(), IoError> {
+        if let Some(parent) = dst_path.parent() {
+            for dir in self.create_dirs(parent)? {
+                trace.dirs.insert(dir.to_string_lossy().into_owned());
+            }
+        }
+        Ok(())
+    }
+
+    /// Carry the directories of `old_trace` over to `trace`, removing those no longer needed.
+    fn carry_dirs(&mut self, old_trace: &PkgTrace, trace: &mut PkgTrace) -> Result<(), IoError> {
+        trace.dirs.extend(old_trace.dirs.iter().cloned());
+        trace.dirs = self.remove_empty_dirs(&trace.dirs)?;
+        Ok(())
     }
 }
 
@@ -209,6 +352,7 @@ mod tests {
                         src: td.join(SRC_FILE_PATH).canonicalize()?,
                         dst: td.join(DST_FILE_PATH)
                     },
+                    LogMessage::CreateDir(td.join("./test_a")),
                     LogMessage::CreateDir(td.join("./test_a/test_b")),
                     LogMessage::CreateSymlink {
                         src: td.join(SRC_DIR_PATH).canonicalize()?,
@@ -321,7 +465,8 @@ mod tests {
                 superset_of([
                     &LogMessage::RemoveSymlink {
                         src: td.join(SRC_FILE_PATH).canonicalize()?,
-                        dst: td.join(DST_FILE_PATH)
+                        dst: td.join(DST_FILE_PATH),
+                        link: LinkStyle::Absolute,
                     },
                     &LogMessage::CreateSymlink {
                         src: td.join(SRC_FILE_PATH).canonicalize()?,
@@ -359,6 +504,11 @@ mod tests {
                     dst: new_dst_path
                 },])
             );
+            expect_eq!(new_trace.dirs.len(), trace.dirs.len() + 1);
+            expect_that!(
+                new_trace.dirs,
+                contains(eq(&td.join("nonexistent_parent").to_string_lossy()))
+            );
 
             Ok(())
         }
@@ -378,10 +528,19 @@ mod tests {
 
             expect_that!(
                 runner.messages(),
-                superset_of([&LogMessage::RemoveSymlink {
-                    src: td.join(SRC_FILE_PATH),
-                    dst: td.join(DST_FILE_PATH)
-                },])
+                superset_of([
+                    &LogMessage::RemoveSymlink {
+                        src: td.join(SRC_FILE_PATH),
+                        dst: td.join(DST_FILE_PATH),
+                        link: LinkStyle::Absolute,
+                    },
+                    &LogMessage::RemoveDir(td.join("./test_pkg")),
+                ])
+            );
+            expect_pred!(!td.join("test_pkg").exists());
+            expect_that!(
+                new_trace.dirs,
+                not(contains(eq(&td.join("./test_pkg").to_string_lossy())))
             );
 
             Ok(())
@@ -472,6 +631,133 @@ mod tests {
             Ok(())
         }
 
+        #[gtest]
+        fn dst_in_trace_points_elsewhere() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+            let td = td.file("other_file", "")?;
+            fs::remove_file(td.join(DST_FILE_PATH))?;
+            std::os::unix::fs::symlink(td.join("other_file"), td.join(DST_FILE_PATH))?;
+
+            let mut runner = common_runner(td.path());
+            let err = runner
+                .load_module(&pkg, Some(&trace))
+                .unwrap_err()
+                .unwrap_load();
+            expect_that!(
+                err,
+                pat!(LoadError::DstPointsElsewhere {
+                    src: "src_file",
+                    dst: &td.join(DST_FILE_PATH)
+                })
+            );
+            expect_that!(
+                td.join(DST_FILE_PATH),
+                is_symlink_for(td.join("other_file"))
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn force_dst_in_trace_points_elsewhere() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+            let td = td.file("other_file", "")?;
+            fs::remove_file(td.join(DST_FILE_PATH))?;
+            std::os::unix::fs::symlink(td.join("other_file"), td.join(DST_FILE_PATH))?;
+
+            let mut runner = common_runner(td.path()).with_force(true);
+            let new_trace = runner.load_module(&pkg, Some(&trace))?;
+
+            expect_eq!(new_trace, trace);
+            expect_that!(
+                td.join(DST_FILE_PATH),
+                is_symlink_for(td.join(SRC_FILE_PATH).canonicalize()?)
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn remove_old_but_dst_points_elsewhere() -> Result<()> {
+            let (td, mut pkg, trace) = setup()?;
+            pkg.remove_map("src_file");
+            let td = td.file("other_file", "")?;
+            fs::remove_file(td.join(DST_FILE_PATH))?;
+            std::os::unix::fs::symlink(td.join("other_file"), td.join(DST_FILE_PATH))?;
+
+            let mut runner = common_runner(td.path());
+            let err = runner
+                .load_module(&pkg, Some(&trace))
+                .unwrap_err()
+                .unwrap_load();
+            expect_that!(
+                err,
+                pat!(LoadError::DstPointsElsewhere {
+                    src: "src_file",
+                    dst: &td.join(DST_FILE_PATH)
+                })
+            );
+            expect_pred!(td.join(DST_FILE_PATH).is_symlink());
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn dst_in_trace_dangles_elsewhere() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+            fs::remove_file(td.join(DST_FILE_PATH))?;
+            std::os::unix::fs::symlink(td.join("moved/src_file"), td.join(DST_FILE_PATH))?;
+
+            let mut runner = common_runner(td.path());
+            let err = runner
+                .load_module(&pkg, Some(&trace))
+                .unwrap_err()
+                .unwrap_load();
+            expect_that!(
+                err,
+                pat!(LoadError::DstPointsElsewhere {
+                    src: "src_file",
+                    dst: &td.join(DST_FILE_PATH)
+                })
+            );
+
+            let mut runner = common_runner(td.path()).with_force(true);
+            runner.load_module(&pkg, Some(&trace))?;
+            expect_that!(
+                td.join(DST_FILE_PATH),
+                is_symlink_for(td.join(SRC_FILE_PATH).canonicalize()?)
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn remove_old_but_dst_dangles_elsewhere() -> Result<()> {
+            let (td, mut pkg, trace) = setup()?;
+            pkg.remove_map("src_file");
+            fs::remove_file(td.join(DST_FILE_PATH))?;
+            std::os::unix::fs::symlink(td.join("moved/src_file"), td.join(DST_FILE_PATH))?;
+
+            let mut runner = common_runner(td.path());
+            let err = runner
+                .load_module(&pkg, Some(&trace))
+                .unwrap_err()
+                .unwrap_load();
+            expect_that!(
+                err,
+                pat!(LoadError::DstPointsElsewhere {
+                    src: "src_file",
+                    dst: &td.join(DST_FILE_PATH)
+                })
+            );
+
+            let mut runner = common_runner(td.path()).with_force(true);
+            runner.load_module(&pkg, Some(&trace))?;
+            expect_false!(td.join(DST_FILE_PATH).is_symlink());
+
+            Ok(())
+        }
+
         #[gtest]
         fn dst_in_trace_but_not_exists() -> Result<()> {
             let (td, pkg, trace) = setup()?;
@@ -492,4 +778,561 @@ mod tests {
             Ok(())
         }
     }
+
+    mod load_with_pkg_dir_changed {
+        use std::collections::{BTreeMap, BTreeSet};
+
+        use super::*;
+
+        const OLD_PKG_DIR: &str = "old_package";
+
+        fn setup() -> Result<(TempDir, NamedPackage, PkgTrace)> {
+            let (td, pkg, _) = common_local_pkg()?;
+            let td = td
+                .dir(format!("{OLD_PKG_DIR}/src_dir"))?
+                .file(format!("{OLD_PKG_DIR}/src_file"), "old_content")?
+                .dir("test_pkg")?
+                .dir("test_a/test_b")?
+                .symlink(DST_FILE_PATH, format!("{OLD_PKG_DIR}/src_file"))?
+                .symlink(DST_DIR_PATH, format!("{OLD_PKG_DIR}/src_dir"))?;
+
+            let trace = PkgTrace {
+                directory: OLD_PKG_DIR.to_string(),
+                revision: None,
+                url: None,
+                maps: BTreeMap::from([
+                    (
+                        "src_file".to_string(),
+                        td.join(DST_FILE_PATH).to_string_lossy().into_owned(),
+                    ),
+                    (
+                        "src_dir".to_string(),
+                        td.join(DST_DIR_PATH).to_string_lossy().into_owned(),
+                    ),
+                ]),
+                backups: BTreeMap::new(),
+                dirs: BTreeSet::new(),
+                link: Default::default(),
+            };
+
+            Ok((td, pkg, trace))
+        }
+
+        #[gtest]
+        fn it_works() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+
+            let mut runner = common_runner(td.path());
+            let new_trace = runner.load_module(&pkg, Some(&trace))?;
+
+            expect_eq!(new_trace.directory, "test_package");
+            expect_eq!(new_trace.maps, trace.maps);
+
+            expect_that!(
+                td.join(DST_FILE_PATH),
+                is_symlink_for(td.join(SRC_FILE_PATH).canonicalize()?)
+            );
+            expect_that!(
+                td.join(DST_DIR_PATH),
+                is_symlink_for(td.join(SRC_DIR_PATH).canonicalize()?)
+            );
+
+            expect_that!(
+                runner.messages(),
+                superset_of([
+                    &LogMessage::RemoveSymlink {
+                        src: td.join(OLD_PKG_DIR).join("src_file"),
+                        dst: td.join(DST_FILE_PATH),
+                        link: LinkStyle::Absolute,
+                    },
+                    &LogMessage::RemoveSymlink {
+                        src: td.join(OLD_PKG_DIR).join("src_dir"),
+                        dst: td.join(DST_DIR_PATH),
+                        link: LinkStyle::Absolute,
+                    },
+                    &LogMessage::CreateSymlink {
+                        src: td.join(SRC_FILE_PATH),
+                        dst: td.join(DST_FILE_PATH)
+                    },
+                    &LogMessage::CreateSymlink {
+                        src: td.join(SRC_DIR_PATH),
+                        dst: td.join(DST_DIR_PATH)
+                    },
+                ])
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn maps_changed() -> Result<()> {
+            let (td, mut pkg, trace) = setup()?;
+            pkg.remove_map("src_dir");
+            pkg.remove_map("src_file");
+            pkg.insert_map("src_file", td.join("new_dest_file").to_string_lossy());
+
+            let mut runner = common_runner(td.path());
+            let new_trace = runner.load_module(&pkg, Some(&trace))?;
+
+            expect_eq!(new_trace.maps.len(), 1);
+            expect_eq!(
+                new_trace.maps["src_file"],
+                td.join("new_dest_file").to_string_lossy()
+            );
+
+            expect_pred!(!td.join(DST_FILE_PATH).exists());
+            expect_pred!(!td.join(DST_DIR_PATH).exists());
+            expect_that!(
+                td.join("new_dest_file"),
+                is_symlink_for(td.join(SRC_FILE_PATH).canonicalize()?)
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn old_dst_not_exists() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+            fs::remove_file(td.join(DST_FILE_PATH))?;
+
+            let mut runner = common_runner(td.path());
+            let new_trace = runner.load_module(&pkg, Some(&trace))?;
+
+            expect_eq!(new_trace.maps, trace.maps);
+            expect_that!(
+                td.join(DST_FILE_PATH),
+                is_symlink_for(td.join(SRC_FILE_PATH).canonicalize()?)
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn old_pkg_dir_removed() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+            fs::remove_dir_all(td.join(OLD_PKG_DIR))?;
+
+            let mut runner = common_runner(td.path());
+            let new_trace = runner.load_module(&pkg, Some(&trace))?;
+
+            expect_eq!(new_trace.maps, trace.maps);
+            expect_that!(
+                td.join(DST_FILE_PATH),
+                is_symlink_for(td.join(SRC_FILE_PATH).canonicalize()?)
+            );
+            expect_that!(
+                td.join(DST_DIR_PATH),
+                is_symlink_for(td.join(SRC_DIR_PATH).canonicalize()?)
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn old_dst_not_a_symlink() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+            fs::remove_file(td.join(DST_FILE_PATH))?;
+            fs::write(td.join(DST_FILE_PATH), "")?;
+
+            let mut runner = common_runner(td.path());
+            let err = runner
+                .load_module(&pkg, Some(&trace))
+                .unwrap_err()
+                .unwrap_load();
+            expect_that!(
+                err,
+                pat!(LoadError::DstNotSymlink {
+                    src: "src_file",
+                    dst: &td.join(DST_FILE_PATH)
+                })
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn old_dst_points_elsewhere() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+            let td = td.file("other_file", "")?;
+            fs::remove_file(td.join(DST_FILE_PATH))?;
+            std::os::unix::fs::symlink(td.join("other_file"), td.join(DST_FILE_PATH))?;
+
+            let mut runner = common_runner(td.path());
+            let err = runner
+                .load_module(&pkg, Some(&trace))
+                .unwrap_err()
+                .unwrap_load();
+            expect_that!(
+                err,
+                pat!(LoadError::DstPointsElsewhere {
+                    src: "src_file",
+                    dst: &td.join(DST_FILE_PATH)
+                })
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn no_pkg_dir() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+            fs::remove_dir_all(td.join("test_package"))?;
+
+            let mut runner = common_runner(td.path());
+            let err = runner
+                .load_module(&pkg, Some(&trace))
+                .unwrap_err()
+                .unwrap_load();
+            expect_that!(err, pat!(LoadError::PkgDirNotFound("test_package")));
+
+            expect_that!(
+                td.join(DST_FILE_PATH),
+                is_symlink_for(td.join(OLD_PKG_DIR).join("src_file").canonicalize()?)
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn rollback_restores_old_symlinks() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+            fs::remove_file(td.join(SRC_FILE_PATH))?;
+
+            let mut runner = common_runner(td.path());
+            let _ = runner.load_module(&pkg, Some(&trace)).unwrap_err();
+            runner.rollback()?;
+
+            expect_that!(
+                td.join(DST_FILE_PATH),
+                is_symlink_for(td.join(OLD_PKG_DIR).join("src_file").canonicalize()?)
+            );
+            expect_that!(
+                td.join(DST_DIR_PATH),
+                is_symlink_for(td.join(OLD_PKG_DIR).join("src_dir").canonicalize()?)
+            );
+
+            Ok(())
+        }
+    }
+
+    mod relative_link {
+        use super::*;
+        use crate::config::VarMap;
+
+        #[gtest]
+        fn it_works() -> Result<()> {
+            let (td, pkg, mut runner) = common_local_pkg()?;
+            let pkg = relative_pkg(&pkg)?;
+
+            let trace = runner.load_module(&pkg, None)?;
+
+            expect_eq!(trace.link, LinkStyle::Relative);
+            expect_eq!(
+                fs::read_link(td.join(DST_FILE_PATH))?,
+                PathBuf::from("../test_package/src_file")
+            );
+            expect_eq!(
+                fs::read_link(td.join(DST_DIR_PATH))?,
+                PathBuf::from("../../test_package/src_dir")
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn change_style() -> Result<()> {
+            let (td, pkg, mut runner) = common_local_pkg()?;
+            let trace = runner.load_module(&pkg, None)?;
+
+            let mut runner = common_runner(td.path());
+            let new_trace = runner.load_module(&relative_pkg(&pkg)?, Some(&trace))?;
+
+            expect_eq!(new_trace.link, LinkStyle::Relative);
+            expect_pred!(fs::read_link(td.join(DST_FILE_PATH))?.is_relative());
+            expect_pred!(fs::read_link(td.join(DST_DIR_PATH))?.is_relative());
+
+            let mut runner = common_runner(td.path());
+            runner.load_module(&pkg, Some(&new_trace))?;
+
+            expect_pred!(fs::read_link(td.join(DST_FILE_PATH))?.is_absolute());
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn rollback_failed_change_style() -> Result<()> {
+            let (td, pkg, mut runner) = common_local_pkg()?;
+            let trace = runner.load_module(&relative_pkg(&pkg)?, None)?;
+
+            let mut maps: Vec<_> = pkg
+                .maps()
+                .iter()
+                .map(|(src, dst)| (src.clone(), dst.as_str().into()))
+                .collect();
+            let dst_missing = td.join("dst_missing").to_string_lossy().into_owned();
+            maps.push(("missing".into(), dst_missing.into()));
+            let pkg = NamedPackage::try_new(
+                pkg.name(),
+                Package {
+                    maps,
+                    ..Default::default()
+                },
+                VarMap::try_new(&[])?,
+            )?;
+
+            let mut runner = common_runner(td.path());
+            let err = runner.load_module(&pkg, Some(&trace)).unwrap_err();
+            expect_that!(
+                err,
+                pat!(RunnerError::LoadModuleError {
+                    source: pat!(LoadError::SrcNotExists(_)),
+                    ..
+                })
+            );
+            runner.rollback()?;
+
+            expect_pred!(fs::read_link(td.join(DST_FILE_PATH))?.is_relative());
+            expect_pred!(fs::read_link(td.join(DST_DIR_PATH))?.is_relative());
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn unload() -> Result<()> {
+            let (td, pkg, mut runner) = common_local_pkg()?;
+            let trace = runner.load_module(&relative_pkg(&pkg)?, None)?;
+
+            let mut runner = common_runner(td.path());
+            runner.unload_module("test_package", &trace)?;
+
+            expect_pred!(!td.join(DST_FILE_PATH).exists());
+            expect_pred!(!td.join(DST_DIR_PATH).is_symlink());
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn rollback_unload() -> Result<()> {
+            let (td, pkg, mut runner) = common_local_pkg()?;
+            let trace = runner.load_module(&relative_pkg(&pkg)?, None)?;
+
+            let mut runner = common_runner(td.path());
+            runner.unload_module("test_package", &trace)?;
+            runner.rollback()?;
+
+            expect_pred!(fs::read_link(td.join(DST_FILE_PATH))?.is_relative());
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn rollback_all_after_absolute_load() -> Result<()> {
+            let (td, pkg, mut runner) = common_local_pkg()?;
+            let trace = runner.load_module(&relative_pkg(&pkg)?, None)?;
+
+            let mut runner = common_runner(td.path());
+            runner.unload_module("test_package", &trace)?;
+            runner.load_module(&pkg, None)?;
+            runner.rollback_all()?;
+
+            expect_pred!(fs::read_link(td.join(DST_FILE_PATH))?.is_relative());
+            expect_pred!(fs::read_link(td.join(DST_DIR_PATH))?.is_relative());
+
+            Ok(())
+        }
+    }
+
+    mod load_git_package {
+        use super::*;
+
+        #[gtest]
+        fn it_works() -> Result<()> {
+            let (td, pkg, mut runner) = common_git_pkg(Package::default())?;
+
+            let trace = runner.load_module(&pkg, None)?;
+
+            expect_eq!(trace.directory, GIT_PKG_DIR);
+            expect_eq!(trace.url.as_ref(), pkg.git().map(|git| &git.url));
+            expect_that!(
+                td.join(DST_FILE_PATH),
+                is_symlink_for(td.join(GIT_PKG_DIR).join("src_file").canonicalize()?)
+            );
+            expect_that!(
+                td.join(DST_DIR_PATH),
+                is_symlink_for(td.join(GIT_PKG_DIR).join("src_dir").canonicalize()?)
+            );
+            expect_eq!(fs::read_to_string(td.join(DST_FILE_PATH))?, "v2");
+
+            expect_that!(
+                runner.messages(),
+                contains(pat!(LogMessage::CloneRepo {
+                    path: &td.join(GIT_PKG_DIR),
+                    ..
+                }))
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn pinned_tag() -> Result<()> {
+            let (td, pkg, mut runner) = common_git_pkg(Package {
+                tag: Some("v1".into()),
+                ..Default::default()
+            })?;
+
+            runner.load_module(&pkg, None)?;
+            expect_eq!(fs::read_to_string(td.join(DST_FILE_PATH))?, "v1");
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn tracked_branch() -> Result<()> {
+            let (td, pkg, mut runner) = common_git_pkg(Package {
+                branch: Some("dev".into()),
+                ..Default::default()
+            })?;
+
+            runner.load_module(&pkg, None)?;
+            expect_eq!(fs::read_to_string(td.join(DST_FILE_PATH))?, "dev");
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn unknown_revision() -> Result<()> {
+            let (td, pkg, mut runner) = common_git_pkg(Package {
+                tag: Some("no_such_tag".into()),
+                ..Default::default()
+            })?;
+
+            let err = runner.load_module(&pkg, None).unwrap_err().unwrap_load();
+            expect_that!(err, pat!(LoadError::Io(_)));
+
+            runner.rollback()?;
+            expect_pred!(!td.join(GIT_PKG_DIR).exists());
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn invalid_url() -> Result<()> {
+            let (td, pkg, mut runner) = common_git_pkg(Package::default())?;
+            fs::remove_dir_all(td.join("remote.git"))?;
+
+            let err = runner.load_module(&pkg, None).unwrap_err().unwrap_load();
+            expect_that!(err, pat!(LoadError::Io(_)));
+            expect_pred!(!td.join(GIT_PKG_DIR).exists());
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn keep_existing_clone() -> Result<()> {
+            let (td, pkg, mut runner) = common_git_pkg(Package::default())?;
+            let trace = runner.load_module(&pkg, None)?;
+            git_commit(&td, "src_file", "v3")?;
+
+            let mut runner = common_runner(td.path());
+            let new_trace = runner.load_module(&pkg, Some(&trace))?;
+
+            expect_eq!(new_trace, trace);
+            expect_eq!(fs::read_to_string(td.join(DST_FILE_PATH))?, "v2");
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn record_revision() -> Result<()> {
+            let (td, pkg, mut runner) = common_git_pkg(Package::default())?;
+
+            let trace = runner.load_module(&pkg, None)?;
+
+            let head = crate::git::resolve(td.join("remote.git"), "main")?;
+            expect_eq!(trace.revision, Some(head));
+
+            Ok(())
+        }
+
+        fn lock_to(td: &TempDir, pkg: &mut NamedPackage, rev: &str) -> Result<String> {
+            let revision = crate::git::resolve(td.join("remote.git"), rev)?;
+            pkg.lock(&PkgLock {
+                url: td.join("remote.git").to_string_lossy().into_owned(),
+                revision: revision.clone(),
+            });
+            Ok(revision)
+        }
+
+        #[gtest]
+        fn locked_revision() -> Result<()> {
+            let (td, mut pkg, mut runner) = common_git_pkg(Package::default())?;
+            let revision = lock_to(&td, &mut pkg, "v1")?;
+
+            let trace = runner.load_module(&pkg, None)?;
+
+            expect_eq!(trace.revision, Some(revision));
+            expect_eq!(fs::read_to_string(td.join(DST_FILE_PATH))?, "v1");
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn locked_revision_with_existing_clone() -> Result<()> {
+            let (td, mut pkg, mut runner) = common_git_pkg(Package::default())?;
+            let trace = runner.load_module(&pkg, None)?;
+            let revision = lock_to(&td, &mut pkg, "v1")?;
+
+            let mut runner = common_runner(td.path());
+            let new_trace = runner.load_module(&pkg, Some(&trace))?;
+
+            expect_eq!(new_trace.revision, Some(revision));
+            expect_eq!(fs::read_to_string(td.join(DST_FILE_PATH))?, "v1");
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn locked_revision_not_fetched() -> Result<()> {
+            let (td, mut pkg, mut runner) = common_git_pkg(Package::default())?;
+            let trace = runner.load_module(&pkg, None)?;
+            git_commit(&td, "src_file", "v3")?;
+            let revision = lock_to(&td, &mut pkg, "main")?;
+
+            let mut runner = common_runner(td.path());
+            let new_trace = runner.load_module(&pkg, Some(&trace))?;
+
+            expect_eq!(new_trace.revision, Some(revision));
+            expect_eq!(fs::read_to_string(td.join(DST_FILE_PATH))?, "v3");
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn url_changed() -> Result<()> {
+            let (td, pkg, mut runner) = common_git_pkg(Package::default())?;
+            let trace = runner.load_module(&pkg, None)?;
+            let other = td.join("other.git").to_string_lossy().into_owned();
+
+            let mut runner = common_runner(td.path());
+            let err = runner
+                .load_module(&git_pkg_with_url(&pkg, &other)?, Some(&trace))
+                .unwrap_err();
+
+            expect_that!(
+                err,
+                pat!(RunnerError::LoadModuleError {
+                    source: pat!(LoadError::RepoUrlChanged {
+                        repo: &td.join(GIT_PKG_DIR),
+                        url: &other,
+                        ..
+                    }),
+                    ..
+                })
+            );
+            expect_eq!(fs::read_to_string(td.join(DST_FILE_PATH))?, "v2");
+
+            Ok(())
+        }
+    }
 }
@@ -1,20 +1,44 @@
+mod conflict;
+mod dry_run;
 mod error;
 mod rw;
 
+mod add;
+mod check;
+mod init;
+mod journal;
 mod load;
+mod relink;
 mod rollback;
+mod status;
 mod unload;
+mod update;
 
-pub use error::{IoError, LoadError, RunnerError, UnloadError};
+pub use conflict::ConflictStrategy;
+pub use error::{AddError, IoError, LoadError, RunnerError, UnloadError};
+pub use status::{BrokenMap, BrokenReason, ModuleStatus};
 
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 
+use crate::config::LinkStyle;
 use crate::logger::{LogMessage, Logger, LoggerOutput};
+use crate::trace::Journal;
+use dry_run::DryRun;
 
 pub struct Runner<O: LoggerOutput> {
     cwd: PathBuf,
     logger: Logger<O>,
+    dry_run: Option<DryRun>,
+    conflict: ConflictStrategy,
+    journal: Option<Journal>,
+    /// Style of the symlinks created for the module being handled
+    link: LinkStyle,
+    /// Remove or replace symlinks even if they point somewhere other than their source
+    force: bool,
+    /// Fail on destinations already gone instead of skipping them with a warning
+    strict: bool,
 }
 
 impl<O: LoggerOutput> Runner<O> {
@@ -26,9 +50,27 @@ impl<O: LoggerOutput> Runner<O> {
         Self {
             cwd: cwd.to_path_buf(),
             logger: Logger::new(output),
+            dry_run: None,
+            conflict: ConflictStrategy::default(),
+            journal: None,
+            link: LinkStyle::default(),
+            force: false,
+            strict: false,
         }
     }
 
+    /// Remove or replace symlinks of a module even if something else re-pointed them.
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Fail to unload a module if any of its symlinks has already been removed.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
     pub fn messages(&self) -> &[LogMessage] {
         self.logger.messages()
     }
@@ -41,8 +83,36 @@ impl<O: LoggerOutput> Runner<O> {
         }
     }
 
+    /// Record `action` in the journal before it is performed, so that it can be undone if the
+    /// operation is interrupted.
+    fn record(&mut self, action: &LogMessage) -> Result<(), IoError> {
+        let Some(journal) = &mut self.journal else {
+            return Ok(());
+        };
+        journal.append(action).map_err(|source| IoError {
+            source,
+            action: "write journal".into(),
+        })
+    }
+
+    /// Drop the journal entry of the action just recorded if it failed, so that `recover` does not
+    /// try to undo it. The error of the action is kept over one of the journal.
+    fn settle<T>(&mut self, result: io::Result<T>) -> io::Result<T> {
+        if result.is_err()
+            && let Some(journal) = &mut self.journal
+        {
+            let _ = journal.discard_last();
+        }
+        result
+    }
+
     pub fn create_dir(&mut self, path: impl AsRef<Path>) -> Result<(), IoError> {
-        fs::create_dir_all(&path).map_err(|source| IoError {
+        self.record(&LogMessage::CreateDir(path.as_ref().into()))?;
+        let result = match &mut self.dry_run {
+            Some(dry_run) => dry_run.create_dir(path.as_ref()),
+            None => fs::create_dir_all(&path),
+        };
+        self.settle(result).map_err(|source| IoError {
             source,
             action: format!("create dir '{}'", path.as_ref().display()),
         })?;
@@ -50,12 +120,40 @@ impl<O: LoggerOutput> Runner<O> {
         Ok(())
     }
 
+    /// Create `path` and its missing ancestors one level at a time, so that each of them can be
+    /// rolled back. Returns the created directories, outermost first.
+    pub fn create_dirs(&mut self, path: impl AsRef<Path>) -> Result<Vec<PathBuf>, IoError> {
+        let mut missing: Vec<PathBuf> = path
+            .as_ref()
+            .ancestors()
+            .take_while(|dir| !dir.as_os_str().is_empty() && !self.exists(dir))
+            .map(Path::to_path_buf)
+            .collect();
+        missing.reverse();
+
+        for dir in &missing {
+            self.create_dir(dir)?;
+        }
+        Ok(missing)
+    }
+
     pub fn create_symlink(
         &mut self,
         src: impl AsRef<Path>,
         dst: impl AsRef<Path>,
     ) -> Result<(), IoError> {
-        crate::fs::create_symlink(&src, &dst).map_err(|source| IoError {
+        self.record(&LogMessage::CreateSymlink {
+            src: src.as_ref().into(),
+            dst: dst.as_ref().into(),
+        })?;
+        let result = match &mut self.dry_run {
+            Some(dry_run) => dry_run.create_symlink(src.as_ref(), dst.as_ref()),
+            None => match self.link {
+                LinkStyle::Absolute => crate::fs::create_symlink(&src, &dst),
+                LinkStyle::Relative => crate::fs::create_relative_symlink(&src, &dst),
+            },
+        };
+        self.settle(result).map_err(|source| IoError {
             source,
             action: format!(
                 "create symlink '{}' for '{}'",
@@ -68,7 +166,12 @@ impl<O: LoggerOutput> Runner<O> {
     }
 
     pub fn remove_dir(&mut self, path: impl AsRef<Path>) -> Result<(), IoError> {
-        fs::remove_dir(&path).map_err(|source| IoError {
+        self.record(&LogMessage::RemoveDir(path.as_ref().into()))?;
+        let result = match &mut self.dry_run {
+            Some(dry_run) => dry_run.remove_dir(path.as_ref()),
+            None => fs::remove_dir(&path),
+        };
+        self.settle(result).map_err(|source| IoError {
             source,
             action: format!("remove dir '{}'", path.as_ref().display()),
         })?;
@@ -81,7 +184,16 @@ impl<O: LoggerOutput> Runner<O> {
         src: impl AsRef<Path>,
         dst: impl AsRef<Path>,
     ) -> Result<(), IoError> {
-        fs::remove_file(&dst).map_err(|source| IoError {
+        self.record(&LogMessage::RemoveSymlink {
+            src: src.as_ref().into(),
+            dst: dst.as_ref().into(),
+            link: self.link,
+        })?;
+        let result = match &mut self.dry_run {
+            Some(dry_run) => dry_run.remove_symlink(dst.as_ref()),
+            None => fs::remove_file(&dst),
+        };
+        self.settle(result).map_err(|source| IoError {
             source,
             action: format!(
                 "remove symlink '{}' for '{}'",
@@ -89,7 +201,208 @@ impl<O: LoggerOutput> Runner<O> {
                 src.as_ref().display()
             ),
         })?;
-        self.logger.remove_symlink(src, dst);
+        self.logger.remove_symlink(src, dst, self.link);
+        Ok(())
+    }
+
+    pub fn move_path(
+        &mut self,
+        from: impl AsRef<Path>,
+        to: impl AsRef<Path>,
+    ) -> Result<(), IoError> {
+        self.record(&LogMessage::MovePath {
+            from: from.as_ref().into(),
+            to: to.as_ref().into(),
+        })?;
+        let result = match &mut self.dry_run {
+            Some(dry_run) => dry_run.move_path(from.as_ref(), to.as_ref()),
+            None => crate::fs::move_path(&from, &to),
+        };
+        self.settle(result).map_err(|source| IoError {
+            source,
+            action: format!(
+                "move '{}' to '{}'",
+                from.as_ref().display(),
+                to.as_ref().display()
+            ),
+        })?;
+        self.logger.move_path(from, to);
+        Ok(())
+    }
+
+    pub fn copy_path(
+        &mut self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+    ) -> Result<(), IoError> {
+        self.record(&LogMessage::CopyPath {
+            src: src.as_ref().into(),
+            dst: dst.as_ref().into(),
+        })?;
+        let result = match &mut self.dry_run {
+            Some(dry_run) => dry_run.copy_path(src.as_ref(), dst.as_ref()),
+            None => crate::fs::copy_all(&src, &dst),
+        };
+        self.settle(result).map_err(|source| IoError {
+            source,
+            action: format!(
+                "copy '{}' to '{}'",
+                src.as_ref().display(),
+                dst.as_ref().display()
+            ),
+        })?;
+        self.logger.copy_path(src, dst);
+        Ok(())
+    }
+
+    /// Remove `dst` which has the same content as `src`, so that it can be restored by copying.
+    pub fn remove_copy(
+        &mut self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+    ) -> Result<(), IoError> {
+        self.record(&LogMessage::RemoveCopy {
+            src: src.as_ref().into(),
+            dst: dst.as_ref().into(),
+        })?;
+        let result = match &mut self.dry_run {
+            Some(dry_run) => dry_run.remove_path(dst.as_ref()),
+            None => crate::fs::remove_all(&dst),
+        };
+        self.settle(result).map_err(|source| IoError {
+            source,
+            action: format!("remove '{}'", dst.as_ref().display()),
+        })?;
+        self.logger.remove_copy(src, dst);
+        Ok(())
+    }
+
+    /// Move `path` to `backup` to make room for a symlink.
+    pub fn backup_path(
+        &mut self,
+        path: impl AsRef<Path>,
+        backup: impl AsRef<Path>,
+    ) -> Result<(), IoError> {
+        self.record(&LogMessage::BackupPath {
+            path: path.as_ref().into(),
+            backup: backup.as_ref().into(),
+        })?;
+        let result = match &mut self.dry_run {
+            Some(dry_run) => dry_run.move_path(path.as_ref(), backup.as_ref()),
+            None => crate::fs::move_path(&path, &backup),
+        };
+        self.settle(result).map_err(|source| IoError {
+            source,
+            action: format!(
+                "back up '{}' to '{}'",
+                path.as_ref().display(),
+                backup.as_ref().display()
+            ),
+        })?;
+        self.logger.backup_path(path, backup);
+        Ok(())
+    }
+
+    pub fn restore_backup(
+        &mut self,
+        backup: impl AsRef<Path>,
+        path: impl AsRef<Path>,
+    ) -> Result<(), IoError> {
+        self.record(&LogMessage::RestoreBackup {
+            backup: backup.as_ref().into(),
+            path: path.as_ref().into(),
+        })?;
+        let result = match &mut self.dry_run {
+            Some(dry_run) => dry_run.move_path(backup.as_ref(), path.as_ref()),
+            None => crate::fs::move_path(&backup, &path),
+        };
+        self.settle(result).map_err(|source| IoError {
+            source,
+            action: format!(
+                "restore '{}' from '{}'",
+                path.as_ref().display(),
+                backup.as_ref().display()
+            ),
+        })?;
+        self.logger.restore_backup(backup, path);
+        Ok(())
+    }
+
+    pub fn clone_repo(&mut self, url: &str, path: impl AsRef<Path>) -> Result<(), IoError> {
+        self.record(&LogMessage::CloneRepo {
+            url: url.into(),
+            path: path.as_ref().into(),
+        })?;
+        let result = match &mut self.dry_run {
+            Some(dry_run) => dry_run.clone_repo(path.as_ref()),
+            None => crate::git::clone(url, &path),
+        };
+        self.settle(result).map_err(|source| IoError {
+            source,
+            action: format!("clone '{url}' into '{}'", path.as_ref().display()),
+        })?;
+        self.logger.clone_repo(url, path);
+        Ok(())
+    }
+
+    pub fn remove_repo(&mut self, url: &str, path: impl AsRef<Path>) -> Result<(), IoError> {
+        self.record(&LogMessage::RemoveRepo {
+            url: url.into(),
+            path: path.as_ref().into(),
+        })?;
+        let result = match &mut self.dry_run {
+            Some(dry_run) => dry_run.remove_repo(path.as_ref()),
+            None => fs::remove_dir_all(&path),
+        };
+        self.settle(result).map_err(|source| IoError {
+            source,
+            action: format!("remove repository '{}'", path.as_ref().display()),
+        })?;
+        self.logger.remove_repo(url, path);
+        Ok(())
+    }
+
+    /// Fetch the remote of a repository, which does not need to be rolled back.
+    pub fn fetch_repo(&mut self, path: impl AsRef<Path>) -> Result<(), IoError> {
+        if self.is_dry_run() {
+            return Ok(());
+        }
+        crate::git::fetch(&path).map_err(|source| IoError {
+            source,
+            action: format!("fetch '{}'", path.as_ref().display()),
+        })
+    }
+
+    pub fn checkout_repo(&mut self, path: impl AsRef<Path>, rev: &str) -> Result<(), IoError> {
+        let path = path.as_ref();
+        if let Some(dry_run) = &self.dry_run
+            && dry_run.is_cloned(path)
+        {
+            self.logger.checkout_repo(path, "HEAD", rev);
+            return Ok(());
+        }
+
+        let map_err = |source| IoError {
+            source,
+            action: format!("checkout '{rev}' in '{}'", path.display()),
+        };
+
+        let from = crate::git::resolve(path, "HEAD").map_err(map_err)?;
+        let to = crate::git::resolve(path, rev).map_err(map_err)?;
+        if from == to {
+            return Ok(());
+        }
+
+        if !self.is_dry_run() {
+            self.record(&LogMessage::CheckoutRepo {
+                path: path.into(),
+                from: from.clone(),
+                to: to.clone(),
+            })?;
+            let result = crate::git::checkout(path, &to);
+            self.settle(result).map_err(map_err)?;
+        }
+        self.logger.checkout_repo(path, from, to);
         Ok(())
     }
 }
@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+
+use super::{IoError, Runner, RunnerError};
+use crate::logger::LoggerOutput;
+use crate::trace::PkgTrace;
+
+impl<O: LoggerOutput> Runner<O> {
+    /// Point every symlink of a loaded module which resolves to anything other than its source
+    /// back to the source, e.g. after the packages have been moved. Returns the number of fixed
+    /// symlinks.
+    ///
+    /// A rollback recreates the removed symlinks for the source, since a stale target can not be
+    /// linked to again.
+    pub fn relink_module(&mut self, name: &str, trace: &PkgTrace) -> Result<usize, RunnerError> {
+        self.logger.relink_module(name);
+        self.link = trace.link;
+        self.relink_module_inner(trace)
+            .map_err(|e| RunnerError::RelinkModuleError {
+                source: e,
+                module: name.to_string(),
+            })
+    }
+
+    fn relink_module_inner(&mut self, trace: &PkgTrace) -> Result<usize, IoError> {
+        let pkg_dir = self.absolute_path_from(&trace.directory);
+
+        let mut relinked = 0;
+        for (src, dst) in &trace.maps {
+            let src_path = pkg_dir.join(src);
+            let dst_path = PathBuf::from(dst);
+            if !self.is_symlink(&dst_path) || self.points_to(&dst_path, &src_path) {
+                continue;
+            }
+
+            self.remove_symlink(&src_path, &dst_path)?;
+            self.create_symlink(&src_path, &dst_path)?;
+            relinked += 1;
+        }
+
+        Ok(relinked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::test_utils::prelude::*;
+
+    const MOVED_PKG_DIR: &str = "moved_package";
+
+    /// Load the package, then move its directory as if the whole repository had been moved.
+    fn setup() -> Result<(TempDir, PkgTrace)> {
+        let (td, pkg, mut runner) = common_local_pkg()?;
+        let trace = runner.load_module(&pkg, None)?;
+
+        fs::rename(td.join("test_package"), td.join(MOVED_PKG_DIR))?;
+        let trace = PkgTrace {
+            directory: MOVED_PKG_DIR.into(),
+            ..trace
+        };
+        Ok((td, trace))
+    }
+
+    #[gtest]
+    fn it_works() -> Result<()> {
+        let (td, trace) = setup()?;
+        let mut runner = common_runner(td.path());
+
+        expect_eq!(runner.relink_module("test_package", &trace)?, 2);
+        expect_that!(
+            td.join(DST_FILE_PATH),
+            is_symlink_for(td.join(MOVED_PKG_DIR).join("src_file"))
+        );
+        expect_that!(
+            td.join(DST_DIR_PATH),
+            is_symlink_for(td.join(MOVED_PKG_DIR).join("src_dir"))
+        );
+        expect_that!(
+            runner.messages(),
+            contains(pat!(LogMessage::RemoveSymlink {
+                src: &td.join(MOVED_PKG_DIR).join("src_file"),
+                dst: &td.join(DST_FILE_PATH),
+                ..
+            }))
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn skip_up_to_date() -> Result<()> {
+        let (td, pkg, mut runner) = common_local_pkg()?;
+        let trace = runner.load_module(&pkg, None)?;
+
+        let mut runner = common_runner(td.path());
+        expect_eq!(runner.relink_module("test_package", &trace)?, 0);
+        expect_eq!(runner.messages().len(), 1);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn skip_missing_and_regular_files() -> Result<()> {
+        let (td, trace) = setup()?;
+        fs::remove_file(td.join(DST_FILE_PATH))?;
+        fs::remove_file(td.join(DST_DIR_PATH))?;
+        let td = td.file(DST_DIR_PATH, "")?;
+
+        let mut runner = common_runner(td.path());
+        expect_eq!(runner.relink_module("test_package", &trace)?, 0);
+        expect_pred!(!td.join(DST_FILE_PATH).exists());
+        expect_pred!(td.join(DST_DIR_PATH).is_file());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn dry_run() -> Result<()> {
+        let (td, trace) = setup()?;
+        let mut runner = common_runner(td.path()).with_dry_run(true);
+
+        expect_eq!(runner.relink_module("test_package", &trace)?, 2);
+        // the second run sees the symlinks relinked by the first one
+        expect_eq!(runner.relink_module("test_package", &trace)?, 0);
+        expect_that!(
+            td.join(DST_FILE_PATH),
+            is_symlink_for(td.join(SRC_FILE_PATH))
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn rollback() -> Result<()> {
+        let (td, trace) = setup()?;
+        let mut runner = common_runner(td.path());
+        runner.relink_module("test_package", &trace)?;
+        runner.rollback()?;
+
+        // the stale target is gone, so the link is recreated for the source
+
+        expect_that!(
+            runner.messages(),
+            contains(pat!(LogMessage::RollbackRelinkModule("test_package")))
+        );
+        expect_that!(
+            td.join(DST_FILE_PATH),
+            is_symlink_for(td.join(MOVED_PKG_DIR).join("src_file"))
+        );
+
+        Ok(())
+    }
+}
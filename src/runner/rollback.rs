@@ -1,4 +1,6 @@
-use super::{Runner, RunnerError};
+use std::mem;
+
+use super::{IoError, Runner, RunnerError};
 use crate::logger::{LogMessage, LoggerOutput};
 
 impl<O: LoggerOutput> Runner<O> {
@@ -6,27 +8,90 @@ impl<O: LoggerOutput> Runner<O> {
         let Some(actions) = self.last_action() else {
             return Err(RunnerError::NoActionToRollback);
         };
+        self.rollback_actions(&actions)
+    }
+
+    /// Roll back every module action which has not been rolled back yet, last first.
+    pub fn rollback_all(&mut self) -> Result<(), RunnerError> {
+        let msgs = self.messages();
+        let mut heads: Vec<_> = (0..msgs.len())
+            .filter(|&i| is_head(&msgs[i]) || is_rollback_head(&msgs[i]))
+            .collect();
+
+        let mut groups = vec![];
+        let mut end = msgs.len();
+        let mut rolled_back = false;
+        while let Some(begin) = heads.pop() {
+            // a rollback always directly follows the action it reverts
+            if is_rollback_head(&msgs[begin]) {
+                rolled_back = true;
+            } else if rolled_back {
+                rolled_back = false;
+            } else {
+                groups.push(msgs[begin..end].to_vec());
+            }
+            end = begin;
+        }
 
+        for actions in groups {
+            self.rollback_actions(&actions)?;
+        }
+        Ok(())
+    }
+
+    fn rollback_actions(&mut self, actions: &[LogMessage]) -> Result<(), RunnerError> {
         let (head, actions) = actions.split_first().unwrap();
         match head {
             LogMessage::LoadModule(module) => self.logger.rollback_load_module(module),
             LogMessage::UnloadModule(module) => self.logger.rollback_unload_module(module),
+            LogMessage::UpdateModule(module) => self.logger.rollback_update_module(module),
+            LogMessage::AddModule(module) => self.logger.rollback_add_module(module),
+            LogMessage::RelinkModule(module) => self.logger.rollback_relink_module(module),
             _ => unreachable!(),
         }
 
         for action in actions.iter().rev() {
-            match action {
-                LogMessage::LoadModule(_)
-                | LogMessage::UnloadModule(_)
-                | LogMessage::RollbackLoadModule(_)
-                | LogMessage::RollbackUnloadModule(_) => unreachable!(),
+            self.undo(action)?;
+        }
 
-                LogMessage::CreateDir(path) => self.remove_dir(path)?,
-                LogMessage::CreateSymlink { src, dst } => self.remove_symlink(src, dst)?,
+        Ok(())
+    }
 
-                LogMessage::RemoveDir(path) => self.create_dir(path)?,
-                LogMessage::RemoveSymlink { src, dst } => self.create_symlink(src, dst)?,
+    /// Perform the inverse of `action`. Module heads and warnings carry no change by themselves.
+    pub(super) fn undo(&mut self, action: &LogMessage) -> Result<(), IoError> {
+        match action {
+            LogMessage::LoadModule(_)
+            | LogMessage::UnloadModule(_)
+            | LogMessage::UpdateModule(_)
+            | LogMessage::AddModule(_)
+            | LogMessage::RelinkModule(_)
+            | LogMessage::RollbackLoadModule(_)
+            | LogMessage::RollbackUnloadModule(_)
+            | LogMessage::RollbackUpdateModule(_)
+            | LogMessage::RollbackAddModule(_)
+            | LogMessage::RollbackRelinkModule(_)
+            | LogMessage::SkipMissingSymlink { .. } => {}
+
+            LogMessage::CreateDir(path) => self.remove_dir(path)?,
+            LogMessage::CreateSymlink { src, dst } => self.remove_symlink(src, dst)?,
+
+            LogMessage::RemoveDir(path) => self.create_dir(path)?,
+            LogMessage::RemoveSymlink { src, dst, link } => {
+                let current = mem::replace(&mut self.link, *link);
+                let result = self.create_symlink(src, dst);
+                self.link = current;
+                result?
             }
+
+            LogMessage::MovePath { from, to } => self.move_path(to, from)?,
+            LogMessage::CopyPath { src, dst } => self.remove_copy(src, dst)?,
+            LogMessage::RemoveCopy { src, dst } => self.copy_path(src, dst)?,
+            LogMessage::BackupPath { path, backup } => self.restore_backup(backup, path)?,
+            LogMessage::RestoreBackup { backup, path } => self.backup_path(path, backup)?,
+
+            LogMessage::CloneRepo { url, path } => self.remove_repo(url, path)?,
+            LogMessage::RemoveRepo { url, path } => self.clone_repo(url, path)?,
+            LogMessage::CheckoutRepo { path, from, .. } => self.checkout_repo(path, from)?,
         }
 
         Ok(())
@@ -35,20 +100,39 @@ impl<O: LoggerOutput> Runner<O> {
     fn last_action(&self) -> Option<Vec<LogMessage>> {
         let msgs = self.messages();
         for i in (0..msgs.len()).rev() {
-            match &msgs[i] {
-                LogMessage::LoadModule(_) | LogMessage::UnloadModule(_) => {
-                    return Some(msgs[i..].to_vec());
-                }
-                LogMessage::RollbackLoadModule(_) | LogMessage::RollbackUnloadModule(_) => {
-                    return None;
-                }
-                _ => {}
+            if is_head(&msgs[i]) {
+                return Some(msgs[i..].to_vec());
+            }
+            if is_rollback_head(&msgs[i]) {
+                return None;
             }
         }
         None
     }
 }
 
+fn is_head(message: &LogMessage) -> bool {
+    matches!(
+        message,
+        LogMessage::LoadModule(_)
+            | LogMessage::UnloadModule(_)
+            | LogMessage::UpdateModule(_)
+            | LogMessage::AddModule(_)
+            | LogMessage::RelinkModule(_)
+    )
+}
+
+fn is_rollback_head(message: &LogMessage) -> bool {
+    matches!(
+        message,
+        LogMessage::RollbackLoadModule(_)
+            | LogMessage::RollbackUnloadModule(_)
+            | LogMessage::RollbackUpdateModule(_)
+            | LogMessage::RollbackAddModule(_)
+            | LogMessage::RollbackRelinkModule(_)
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
@@ -96,12 +180,15 @@ mod tests {
                     LogMessage::RollbackLoadModule("test_package".into()),
                     LogMessage::RemoveSymlink {
                         src: td.join(SRC_DIR_PATH).canonicalize()?,
-                        dst: td.join(DST_DIR_PATH)
+                        dst: td.join(DST_DIR_PATH),
+                        link: LinkStyle::Absolute,
                     },
                     LogMessage::RemoveDir(td.join("./test_a/test_b")),
+                    LogMessage::RemoveDir(td.join("./test_a")),
                     LogMessage::RemoveSymlink {
                         src: td.join(SRC_FILE_PATH).canonicalize()?,
-                        dst: td.join(DST_FILE_PATH)
+                        dst: td.join(DST_FILE_PATH),
+                        link: LinkStyle::Absolute,
                     },
                     LogMessage::RemoveDir(td.join("./test_pkg")),
                 ]
@@ -129,7 +216,8 @@ mod tests {
                     LogMessage::RollbackLoadModule("test_package".into()),
                     LogMessage::RemoveSymlink {
                         src: td.join(SRC_FILE_PATH).canonicalize()?,
-                        dst: td.join(DST_FILE_PATH)
+                        dst: td.join(DST_FILE_PATH),
+                        link: LinkStyle::Absolute,
                     },
                     LogMessage::RemoveDir(td.join("./test_pkg")),
                 ]
@@ -164,7 +252,8 @@ mod tests {
                     LogMessage::RollbackLoadModule("test_package".into()),
                     LogMessage::RemoveSymlink {
                         src: td.join(new_src_file).canonicalize()?,
-                        dst: td.join("new_dst_file")
+                        dst: td.join("new_dst_file"),
+                        link: LinkStyle::Absolute,
                     }
                 ]
             );
@@ -173,6 +262,56 @@ mod tests {
         }
     }
 
+    mod rollback_all {
+        use super::*;
+
+        #[gtest]
+        fn every_action() -> Result<()> {
+            let (td, mut pkg, mut runner) = common_local_pkg()?;
+            let trace = runner.load_module(&pkg, None)?;
+
+            let td = td.file("test_package/new_src_file", "")?;
+            pkg.insert_map("new_src_file", td.join("new_dst_file").to_string_lossy());
+            runner.load_module(&pkg, Some(&trace))?;
+
+            runner.rollback_all()?;
+
+            expect_pred!(!td.join(DST_DIR_PATH).exists());
+            expect_pred!(!td.join(DST_FILE_PATH).exists());
+            expect_pred!(!td.join("new_dst_file").exists());
+
+            let err = runner.rollback().unwrap_err();
+            expect_that!(err, pat!(RunnerError::NoActionToRollback));
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn skip_rolled_back() -> Result<()> {
+            let (td, mut pkg, mut runner) = common_local_pkg()?;
+            let trace = runner.load_module(&pkg, None)?;
+
+            pkg.insert_map("missing", td.join("new_dst_file").to_string_lossy());
+            let _ = runner.load_module(&pkg, Some(&trace)).unwrap_err();
+            runner.rollback()?;
+
+            runner.rollback_all()?;
+
+            expect_pred!(!td.join(DST_DIR_PATH).exists());
+            expect_pred!(!td.join(DST_FILE_PATH).exists());
+            expect_eq!(
+                runner
+                    .messages()
+                    .iter()
+                    .filter(|m| matches!(m, LogMessage::RollbackLoadModule(_)))
+                    .count(),
+                2
+            );
+
+            Ok(())
+        }
+    }
+
     mod rollback_unload_module {
         use super::*;
 
@@ -223,7 +362,7 @@ mod tests {
             let trace = runner.load_module(&pkg, None)?;
             fs::remove_file(td.join(DST_FILE_PATH))?;
 
-            let mut runner = common_runner(td.path());
+            let mut runner = common_runner(td.path()).with_strict(true);
             let _ = runner.unload_module("test_package", &trace).unwrap_err();
 
             let msgs = runner.messages()[1..].to_vec();
@@ -231,6 +370,7 @@ mod tests {
             let unload_src_dir = runner.messages().contains(&LogMessage::RemoveSymlink {
                 src: td.join(SRC_DIR_PATH),
                 dst: td.join(DST_DIR_PATH),
+                link: LinkStyle::Absolute,
             });
 
             runner.rollback()?;
@@ -257,4 +397,49 @@ mod tests {
             Ok(())
         }
     }
+
+    mod rollback_update_module {
+        use super::*;
+
+        #[gtest]
+        fn after_success() -> Result<()> {
+            let (td, pkg, mut runner) = common_git_pkg(Package::default())?;
+            let trace = runner.load_module(&pkg, None)?;
+            git_commit(&td, "src_file", "v3")?;
+
+            let mut runner = common_runner(td.path());
+            runner.update_module(&pkg, Some(&trace))?;
+            let rollback_begin = runner.messages().len();
+
+            runner.rollback()?;
+
+            expect_eq!(fs::read_to_string(td.join(DST_FILE_PATH))?, "v2");
+            expect_that!(
+                runner.messages()[rollback_begin..],
+                elements_are![
+                    pat!(LogMessage::RollbackUpdateModule("test_package")),
+                    pat!(LogMessage::CheckoutRepo {
+                        path: &td.join(GIT_PKG_DIR),
+                        ..
+                    })
+                ]
+            );
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn remove_fresh_clone() -> Result<()> {
+            let (td, pkg, mut runner) = common_git_pkg(Package::default())?;
+            runner.update_module(&pkg, None)?;
+
+            runner.rollback()?;
+
+            expect_pred!(!td.join(GIT_PKG_DIR).exists());
+            expect_pred!(!td.join(DST_FILE_PATH).exists());
+            expect_pred!(!td.join(DST_DIR_PATH).exists());
+
+            Ok(())
+        }
+    }
 }
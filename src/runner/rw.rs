@@ -3,19 +3,39 @@ use std::path::PathBuf;
 use super::{Runner, RunnerError};
 use crate::config::Config;
 use crate::logger::LoggerOutput;
-use crate::meta::{PKGS_DIR, TOML_CONFIG_FILE, YAML_CONFIG_FILE, YML_CONFIG_FILE};
+use crate::meta::{LOCK_FILE, PKGS_DIR, TOML_CONFIG_FILE, YAML_CONFIG_FILE, YML_CONFIG_FILE};
+use crate::trace::Lock;
 
 impl<O: LoggerOutput> Runner<O> {
     pub fn read_config(&self) -> Result<Config, RunnerError> {
+        let config = Config::read(&self.config_path()?)?;
+        Ok(config)
+    }
+
+    pub fn config_path(&self) -> Result<PathBuf, RunnerError> {
         let candidates = [TOML_CONFIG_FILE, YAML_CONFIG_FILE, YML_CONFIG_FILE];
-        for name in candidates {
-            let path = self.cwd.join(name);
-            if path.exists() {
-                let config = Config::read(&path)?;
-                return Ok(config);
-            }
+        candidates
+            .into_iter()
+            .map(|name| self.cwd.join(name))
+            .find(|path| path.exists())
+            .ok_or(RunnerError::ConfigNotFound)
+    }
+
+    pub fn read_lock(&self) -> Result<Lock, RunnerError> {
+        let path = self.cwd.join(LOCK_FILE);
+        if !path.exists() {
+            return Ok(Lock::default());
+        }
+        Ok(Lock::read_from_file(&path)?)
+    }
+
+    /// Write the lock file, skipping it when there is nothing to lock and no lock file yet.
+    pub fn write_lock(&self, lock: &Lock) -> Result<(), RunnerError> {
+        let path = self.cwd.join(LOCK_FILE);
+        if lock.packages.is_empty() && !path.exists() {
+            return Ok(());
         }
-        Err(RunnerError::ConfigNotFound)
+        Ok(lock.write_to_file(&path)?)
     }
 
     pub fn create_pkgs_dir(&mut self) -> Result<PathBuf, RunnerError> {
@@ -143,6 +163,69 @@ mod tests {
         }
     }
 
+    mod lock {
+        use std::collections::BTreeMap;
+
+        use super::*;
+        use crate::trace::PkgLock;
+
+        fn lock() -> Lock {
+            Lock {
+                packages: BTreeMap::from([(
+                    "test".into(),
+                    PkgLock {
+                        url: "url".into(),
+                        revision: "abc123".into(),
+                    },
+                )]),
+            }
+        }
+
+        #[gtest]
+        fn read_without_lock_file() -> Result<()> {
+            let td = TempDir::new()?;
+            let runner = common_runner(td.path());
+
+            expect_eq!(runner.read_lock()?, Lock::default());
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn write_and_read() -> Result<()> {
+            let td = TempDir::new()?;
+            let runner = common_runner(td.path());
+
+            runner.write_lock(&lock())?;
+            expect_pred!(td.join(LOCK_FILE).is_file());
+            expect_eq!(runner.read_lock()?, lock());
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn skip_empty_lock() -> Result<()> {
+            let td = TempDir::new()?;
+            let runner = common_runner(td.path());
+
+            runner.write_lock(&Lock::default())?;
+            expect_pred!(!td.join(LOCK_FILE).exists());
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn read_invalid_lock_file() -> Result<()> {
+            let td = TempDir::new()?.file(LOCK_FILE, "invalid lock")?;
+            let runner = common_runner(td.path());
+
+            let err = runner.read_lock().unwrap_err();
+            expect_that!(err, pat!(RunnerError::LockFileError(_)));
+
+            Ok(())
+        }
+    }
+
     mod create_pkgs_dir {
 
         use super::*;
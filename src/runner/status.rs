@@ -0,0 +1,318 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+
+use super::Runner;
+use crate::config::NamedPackage;
+use crate::fs::symlink_points_to;
+use crate::logger::LoggerOutput;
+use crate::trace::PkgTrace;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleStatus {
+    NotLoaded,
+    UpToDate,
+    NeedsReload,
+    Broken(Vec<BrokenMap>),
+    Orphaned,
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenMap {
+    pub src: String,
+    pub dst: PathBuf,
+    pub reason: BrokenReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BrokenReason {
+    Missing,
+    NotSymlink,
+    PointsElsewhere,
+    Dangling,
+}
+
+impl ModuleStatus {
+    pub fn needs_attention(&self) -> bool {
+        matches!(
+            self,
+            ModuleStatus::NeedsReload | ModuleStatus::Broken(_) | ModuleStatus::Orphaned
+        )
+    }
+}
+
+impl Display for ModuleStatus {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ModuleStatus::NotLoaded => write!(f, "not loaded"),
+            ModuleStatus::UpToDate => write!(f, "up to date"),
+            ModuleStatus::NeedsReload => write!(f, "needs reload"),
+            ModuleStatus::Broken(_) => write!(f, "broken"),
+            ModuleStatus::Orphaned => write!(f, "orphaned"),
+            ModuleStatus::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+impl Display for BrokenMap {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let (src, dst) = (&self.src, self.dst.display());
+        match self.reason {
+            BrokenReason::Missing => write!(f, "'{dst}' for '{src}' does not exist"),
+            BrokenReason::NotSymlink => write!(f, "'{dst}' for '{src}' is not a symlink"),
+            BrokenReason::PointsElsewhere => {
+                write!(f, "'{dst}' for '{src}' points to another path")
+            }
+            BrokenReason::Dangling => write!(f, "'{dst}' for '{src}' is a dangling symlink"),
+        }
+    }
+}
+
+impl<O: LoggerOutput> Runner<O> {
+    /// Compare a package in the configuration with its trace and the filesystem.
+    ///
+    /// `package` is `None` if the package has been removed from the configuration. A loaded
+    /// package skipped by its condition is orphaned as well.
+    pub fn module_status(
+        &self,
+        package: Option<&NamedPackage>,
+        trace: Option<&PkgTrace>,
+    ) -> ModuleStatus {
+        let active = package.is_none_or(NamedPackage::is_active);
+        let Some(trace) = trace else {
+            return if active {
+                ModuleStatus::NotLoaded
+            } else {
+                ModuleStatus::Skipped
+            };
+        };
+        let Some(package) = package.filter(|_| active) else {
+            return ModuleStatus::Orphaned;
+        };
+
+        let broken = self.broken_maps(trace);
+        if !broken.is_empty() {
+            return ModuleStatus::Broken(broken);
+        }
+
+        let maps: BTreeMap<_, _> = package.maps().iter().cloned().collect();
+        if package.get_directory() != trace.directory
+            || maps != trace.maps
+            || package.link() != trace.link
+        {
+            return ModuleStatus::NeedsReload;
+        }
+
+        ModuleStatus::UpToDate
+    }
+
+    fn broken_maps(&self, trace: &PkgTrace) -> Vec<BrokenMap> {
+        let pkg_dir = self.absolute_path_from(&trace.directory);
+
+        trace
+            .maps
+            .iter()
+            .filter_map(|(src, dst)| {
+                let dst_path = PathBuf::from(dst);
+                let reason = if !dst_path.is_symlink() {
+                    if dst_path.exists() {
+                        BrokenReason::NotSymlink
+                    } else {
+                        BrokenReason::Missing
+                    }
+                } else if !symlink_points_to(&dst_path, pkg_dir.join(src)).unwrap_or(false) {
+                    BrokenReason::PointsElsewhere
+                } else if !dst_path.exists() {
+                    BrokenReason::Dangling
+                } else {
+                    return None;
+                };
+
+                Some(BrokenMap {
+                    src: src.clone(),
+                    dst: dst_path,
+                    reason,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::config::{Condition, Package, VarMap};
+    use crate::test_utils::prelude::*;
+
+    fn setup() -> Result<(TempDir, NamedPackage, PkgTrace)> {
+        let (td, pkg, mut runner) = common_local_pkg()?;
+        let trace = runner.load_module(&pkg, None)?;
+        Ok((td, pkg, trace))
+    }
+
+    #[gtest]
+    fn not_loaded() -> Result<()> {
+        let (_td, pkg, runner) = common_local_pkg()?;
+
+        let status = runner.module_status(Some(&pkg), None);
+        expect_eq!(status, ModuleStatus::NotLoaded);
+        expect_false!(status.needs_attention());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn up_to_date() -> Result<()> {
+        let (td, pkg, trace) = setup()?;
+        let runner = common_runner(td.path());
+
+        let status = runner.module_status(Some(&pkg), Some(&trace));
+        expect_eq!(status, ModuleStatus::UpToDate);
+        expect_false!(status.needs_attention());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn needs_reload() -> Result<()> {
+        let (td, mut pkg, trace) = setup()?;
+        pkg.remove_map("src_file");
+        let runner = common_runner(td.path());
+
+        let status = runner.module_status(Some(&pkg), Some(&trace));
+        expect_eq!(status, ModuleStatus::NeedsReload);
+        expect_true!(status.needs_attention());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn relative_link() -> Result<()> {
+        let (td, pkg, _) = common_local_pkg()?;
+        let pkg = relative_pkg(&pkg)?;
+        let trace = common_runner(td.path()).load_module(&pkg, None)?;
+        let runner = common_runner(td.path());
+
+        let status = runner.module_status(Some(&pkg), Some(&trace));
+        expect_eq!(status, ModuleStatus::UpToDate);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn link_style_changed() -> Result<()> {
+        let (td, pkg, trace) = setup()?;
+        let runner = common_runner(td.path());
+
+        let status = runner.module_status(Some(&relative_pkg(&pkg)?), Some(&trace));
+        expect_eq!(status, ModuleStatus::NeedsReload);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn orphaned() -> Result<()> {
+        let (td, _, trace) = setup()?;
+        let runner = common_runner(td.path());
+
+        let status = runner.module_status(None, Some(&trace));
+        expect_eq!(status, ModuleStatus::Orphaned);
+        expect_true!(status.needs_attention());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn skipped() -> Result<()> {
+        let (td, _, trace) = setup()?;
+        let runner = common_runner(td.path());
+        let pkg = NamedPackage::try_new(
+            "test_package",
+            Package {
+                when: Some(Condition {
+                    os: vec!["none".into()],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            VarMap::try_new(&[])?,
+        )?;
+
+        let status = runner.module_status(Some(&pkg), None);
+        expect_eq!(status, ModuleStatus::Skipped);
+        expect_false!(status.needs_attention());
+
+        let status = runner.module_status(Some(&pkg), Some(&trace));
+        expect_eq!(status, ModuleStatus::Orphaned);
+
+        Ok(())
+    }
+
+    mod broken {
+        use super::*;
+
+        fn broken_file(td: &TempDir, reason: BrokenReason) -> ModuleStatus {
+            ModuleStatus::Broken(vec![BrokenMap {
+                src: "src_file".into(),
+                dst: td.join(DST_FILE_PATH),
+                reason,
+            }])
+        }
+
+        #[gtest]
+        fn dst_missing() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+            fs::remove_file(td.join(DST_FILE_PATH))?;
+            let runner = common_runner(td.path());
+
+            let status = runner.module_status(Some(&pkg), Some(&trace));
+            expect_eq!(status, broken_file(&td, BrokenReason::Missing));
+            expect_true!(status.needs_attention());
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn dst_not_symlink() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+            fs::remove_file(td.join(DST_FILE_PATH))?;
+            fs::write(td.join(DST_FILE_PATH), "")?;
+            let runner = common_runner(td.path());
+
+            let status = runner.module_status(Some(&pkg), Some(&trace));
+            expect_eq!(status, broken_file(&td, BrokenReason::NotSymlink));
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn dst_points_elsewhere() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+            fs::remove_file(td.join(DST_FILE_PATH))?;
+            let td = td
+                .file("another_file", "")?
+                .symlink(DST_FILE_PATH, "another_file")?;
+            let runner = common_runner(td.path());
+
+            let status = runner.module_status(Some(&pkg), Some(&trace));
+            expect_eq!(status, broken_file(&td, BrokenReason::PointsElsewhere));
+
+            Ok(())
+        }
+
+        #[gtest]
+        fn src_removed() -> Result<()> {
+            let (td, pkg, trace) = setup()?;
+            fs::remove_file(td.join(SRC_FILE_PATH))?;
+            let runner = common_runner(td.path());
+
+            let status = runner.module_status(Some(&pkg), Some(&trace));
+            expect_eq!(status, broken_file(&td, BrokenReason::Dangling));
+
+            Ok(())
+        }
+    }
+}
@@ -1,12 +1,15 @@
-use std::path::PathBuf;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 
-use super::{Runner, RunnerError, UnloadError};
+use super::{IoError, Runner, RunnerError, UnloadError};
 use crate::logger::LoggerOutput;
 use crate::trace::PkgTrace;
 
 impl<O: LoggerOutput> Runner<O> {
     pub fn unload_module(&mut self, name: &str, trace: &PkgTrace) -> Result<(), RunnerError> {
         self.logger.unload_module(name);
+        self.link = trace.link;
         self.unload_module_inner(trace)
             .map_err(|e| RunnerError::UnloadModuleError {
                 source: e,
@@ -19,25 +22,67 @@ impl<O: LoggerOutput> Runner<O> {
 
         for (src, dst) in &trace.maps {
             let dst_path = PathBuf::from(dst);
-            if !dst_path.exists() {
-                return Err(UnloadError::DstNotFound {
-                    src: src.clone(),
-                    dst: dst_path,
-                });
-            }
-            if !dst_path.is_symlink() {
-                return Err(UnloadError::DstNotSymlink {
-                    src: src.clone(),
-                    dst: dst_path,
-                });
+            let src_path = pkg_dir.join(src);
+
+            // a dangling symlink does not exist but is still there to be removed
+            if !self.exists(&dst_path) && !self.is_symlink(&dst_path) {
+                if self.strict {
+                    return Err(UnloadError::DstNotFound {
+                        src: src.clone(),
+                        dst: dst_path,
+                    });
+                }
+                self.logger.skip_missing_symlink(src_path, &dst_path);
+            } else {
+                if !self.is_symlink(&dst_path) {
+                    return Err(UnloadError::DstNotSymlink {
+                        src: src.clone(),
+                        dst: dst_path,
+                    });
+                }
+                if self.is_foreign(&dst_path, &src_path) {
+                    return Err(UnloadError::DstPointsElsewhere {
+                        src: src.clone(),
+                        dst: dst_path,
+                    });
+                }
+                self.remove_symlink(src_path, &dst_path)?;
             }
 
-            let src_path = pkg_dir.join(src);
-            self.remove_symlink(src_path, dst_path)?;
+            if let Some(backup) = trace.backups.get(dst) {
+                self.restore_backup(backup, dst_path)?;
+            }
         }
 
+        self.remove_empty_dirs(&trace.dirs)?;
+
         Ok(())
     }
+
+    /// Remove the empty directories among `dirs`, deepest first, and return those still in use.
+    pub(super) fn remove_empty_dirs(
+        &mut self,
+        dirs: &BTreeSet<String>,
+    ) -> Result<BTreeSet<String>, IoError> {
+        let mut sorted: Vec<&String> = dirs.iter().collect();
+        sorted.sort_by_key(|dir| Reverse(Path::new(dir).components().count()));
+
+        let mut kept = BTreeSet::new();
+        for dir in sorted {
+            let path = Path::new(dir);
+            if !self.exists(path) || self.is_symlink(path) {
+                continue;
+            }
+
+            if self.is_empty_dir(path) {
+                self.remove_dir(path)?;
+            } else {
+                kept.insert(dir.clone());
+            }
+        }
+
+        Ok(kept)
+    }
 }
 
 #[cfg(test)]
@@ -63,13 +108,14 @@ mod tests {
         expect_pred!(!td.join(DST_DIR_PATH).exists());
 
         let messages = runner.messages();
-        expect_eq!(messages.len(), 3);
+        expect_eq!(messages.len(), 6);
         expect_that!(messages[0], pat!(LogMessage::UnloadModule("test_package")));
         expect_that!(
             messages,
             contains(pat!(LogMessage::RemoveSymlink {
                 src: &td.join(SRC_DIR_PATH),
                 dst: &td.join(DST_DIR_PATH),
+                ..
             }))
         );
         expect_that!(
@@ -77,6 +123,67 @@ mod tests {
             contains(pat!(LogMessage::RemoveSymlink {
                 src: &td.join(SRC_FILE_PATH),
                 dst: &td.join(DST_FILE_PATH),
+                ..
+            }))
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn remove_created_dirs() -> Result<()> {
+        let (td, trace, mut runner) = setup()?;
+        expect_eq!(
+            trace.dirs,
+            BTreeSet::from(
+                ["./test_pkg", "./test_a", "./test_a/test_b"]
+                    .map(|dir| td.join(dir).to_string_lossy().into_owned())
+            )
+        );
+
+        runner.unload_module("test_package", &trace)?;
+
+        expect_pred!(!td.join("test_pkg").exists());
+        expect_pred!(!td.join("test_a").exists());
+        expect_that!(
+            runner.messages()[3..],
+            elements_are![
+                eq(&LogMessage::RemoveDir(td.join("./test_a/test_b"))),
+                eq(&LogMessage::RemoveDir(td.join("./test_a"))),
+                eq(&LogMessage::RemoveDir(td.join("./test_pkg"))),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn keep_dirs_with_other_files() -> Result<()> {
+        let (td, trace, mut runner) = setup()?;
+        let td = td.file("./test_a/unrelated", "")?;
+
+        runner.unload_module("test_package", &trace)?;
+
+        expect_pred!(!td.join("test_pkg").exists());
+        expect_pred!(!td.join("test_a/test_b").exists());
+        expect_pred!(td.join("test_a/unrelated").exists());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn skip_missing_dst() -> Result<()> {
+        let (td, trace, mut runner) = setup()?;
+        fs::remove_file(td.join(DST_FILE_PATH))?;
+
+        runner.unload_module("test_package", &trace)?;
+
+        expect_pred!(!td.join(DST_DIR_PATH).exists());
+        expect_that!(
+            runner.messages(),
+            contains(pat!(LogMessage::SkipMissingSymlink {
+                src: &td.join(SRC_FILE_PATH),
+                dst: &td.join(DST_FILE_PATH),
             }))
         );
 
@@ -84,11 +191,32 @@ mod tests {
     }
 
     #[gtest]
-    fn dst_not_exists() -> Result<()> {
+    fn remove_dangling_symlink() -> Result<()> {
         let (td, trace, mut runner) = setup()?;
+        fs::remove_file(td.join(SRC_FILE_PATH))?;
+
+        runner.unload_module("test_package", &trace)?;
+
+        expect_pred!(!td.join(DST_FILE_PATH).is_symlink());
+        expect_that!(
+            runner.messages(),
+            contains(pat!(LogMessage::RemoveSymlink {
+                src: &td.join(SRC_FILE_PATH),
+                dst: &td.join(DST_FILE_PATH),
+                ..
+            }))
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn strict_dst_not_exists() -> Result<()> {
+        let (td, trace, runner) = setup()?;
         fs::remove_file(td.join(DST_FILE_PATH))?;
 
         let err = runner
+            .with_strict(true)
             .unload_module("test_package", &trace)
             .unwrap_err()
             .unwrap_unload();
@@ -123,4 +251,43 @@ mod tests {
 
         Ok(())
     }
+
+    #[gtest]
+    fn dst_points_elsewhere() -> Result<()> {
+        let (td, trace, mut runner) = setup()?;
+        let td = td.file("other_file", "")?;
+        fs::remove_file(td.join(DST_FILE_PATH))?;
+        std::os::unix::fs::symlink(td.join("other_file"), td.join(DST_FILE_PATH))?;
+
+        let err = runner
+            .unload_module("test_package", &trace)
+            .unwrap_err()
+            .unwrap_unload();
+        expect_that!(
+            err,
+            pat!(UnloadError::DstPointsElsewhere {
+                src: "src_file",
+                dst: &td.join(DST_FILE_PATH)
+            })
+        );
+        expect_pred!(td.join(DST_FILE_PATH).is_symlink());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn force_dst_points_elsewhere() -> Result<()> {
+        let (td, trace, runner) = setup()?;
+        let td = td.file("other_file", "")?;
+        fs::remove_file(td.join(DST_FILE_PATH))?;
+        std::os::unix::fs::symlink(td.join("other_file"), td.join(DST_FILE_PATH))?;
+
+        runner
+            .with_force(true)
+            .unload_module("test_package", &trace)?;
+        expect_pred!(!td.join(DST_FILE_PATH).is_symlink());
+        expect_pred!(td.join("other_file").exists());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,139 @@
+use super::{LoadError, Runner, RunnerError};
+use crate::config::NamedPackage;
+use crate::logger::LoggerOutput;
+use crate::trace::PkgTrace;
+
+impl<O: LoggerOutput> Runner<O> {
+    pub fn update_module(
+        &mut self,
+        package: &NamedPackage,
+        trace: Option<&PkgTrace>,
+    ) -> Result<PkgTrace, RunnerError> {
+        self.logger.update_module(package.name());
+        self.update_source(package)
+            .and_then(|()| self.load_module_inner(package, trace))
+            .map_err(|e| RunnerError::UpdateModuleError {
+                source: e,
+                module: package.name().to_string(),
+            })
+    }
+
+    fn update_source(&mut self, package: &NamedPackage) -> Result<(), LoadError> {
+        let Some(git) = package.git() else {
+            return Ok(());
+        };
+
+        let repo = self.absolute_path_from(package.get_directory());
+        if !repo.exists() {
+            return Ok(());
+        }
+
+        self.check_origin(&repo, &git.url)?;
+        self.fetch_repo(&repo)?;
+        self.checkout_repo(&repo, &git.revision.target())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::test_utils::prelude::*;
+
+    #[gtest]
+    fn move_to_new_commit() -> Result<()> {
+        let (td, pkg, mut runner) = common_git_pkg(Package::default())?;
+        let trace = runner.load_module(&pkg, None)?;
+        git_commit(&td, "src_file", "v3")?;
+
+        let mut runner = common_runner(td.path());
+        let new_trace = runner.update_module(&pkg, Some(&trace))?;
+
+        expect_eq!(new_trace.maps, trace.maps);
+        expect_ne!(new_trace.revision, trace.revision);
+        expect_eq!(fs::read_to_string(td.join(DST_FILE_PATH))?, "v3");
+
+        expect_that!(
+            runner.messages()[0],
+            pat!(LogMessage::UpdateModule("test_package"))
+        );
+        expect_that!(
+            runner.messages(),
+            contains(pat!(LogMessage::CheckoutRepo {
+                path: &td.join(GIT_PKG_DIR),
+                ..
+            }))
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn pinned_tag_not_moved() -> Result<()> {
+        let (td, pkg, mut runner) = common_git_pkg(Package {
+            tag: Some("v1".into()),
+            ..Default::default()
+        })?;
+        let trace = runner.load_module(&pkg, None)?;
+        git_commit(&td, "src_file", "v3")?;
+
+        let mut runner = common_runner(td.path());
+        runner.update_module(&pkg, Some(&trace))?;
+
+        expect_eq!(fs::read_to_string(td.join(DST_FILE_PATH))?, "v1");
+        expect_eq!(runner.messages().len(), 1);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn url_changed() -> Result<()> {
+        let (td, pkg, mut runner) = common_git_pkg(Package::default())?;
+        let trace = runner.load_module(&pkg, None)?;
+        let other = td.join("other.git").to_string_lossy().into_owned();
+
+        let mut runner = common_runner(td.path());
+        let err = runner
+            .update_module(&git_pkg_with_url(&pkg, &other)?, Some(&trace))
+            .unwrap_err();
+
+        expect_that!(
+            err,
+            pat!(RunnerError::UpdateModuleError {
+                source: pat!(LoadError::RepoUrlChanged { .. }),
+                ..
+            })
+        );
+        expect_eq!(runner.messages().len(), 1);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn not_cloned_yet() -> Result<()> {
+        let (td, pkg, mut runner) = common_git_pkg(Package::default())?;
+
+        let trace = runner.update_module(&pkg, None)?;
+
+        expect_eq!(trace.directory, GIT_PKG_DIR);
+        expect_eq!(fs::read_to_string(td.join(DST_FILE_PATH))?, "v2");
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn local_package() -> Result<()> {
+        let (td, pkg, mut runner) = common_local_pkg()?;
+
+        let trace = runner.update_module(&pkg, None)?;
+
+        expect_eq!(trace.directory, "test_package");
+        expect_that!(
+            td.join(DST_FILE_PATH),
+            is_symlink_for(td.join(SRC_FILE_PATH).canonicalize()?)
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use googletest::Result;
+
+use super::file::TempDir;
+
+const REMOTE_WORK: &str = "remote_work";
+const REMOTE: &str = "remote.git";
+
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let status = Command::new("git")
+        .args(["-c", "user.name=pkgs", "-c", "user.email=pkgs@example.com"])
+        .args(["-c", "commit.gpgsign=false", "-c", "tag.gpgsign=false"])
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .status()?;
+    if !status.success() {
+        Err(io::Error::other(format!("git {} failed", args.join(" "))))?;
+    }
+    Ok(())
+}
+
+/// Create a bare repository with the following history:
+///
+/// - `main`: `src_file` is "v1" (tagged `v1`), then "v2"
+/// - `dev`: branched from `v1`, `src_file` is "dev"
+///
+/// Both revisions contain `src_dir/`.
+pub fn git_remote(td: &TempDir) -> Result<PathBuf> {
+    let work = td.join(REMOTE_WORK);
+    fs::create_dir_all(work.join("src_dir"))?;
+    fs::write(work.join("src_dir/file"), "")?;
+    fs::write(work.join("src_file"), "v1")?;
+
+    git(
+        td.path(),
+        &["init", "--quiet", "--initial-branch=main", REMOTE_WORK],
+    )?;
+    git(&work, &["add", "."])?;
+    git(&work, &["commit", "--quiet", "-m", "v1"])?;
+    git(&work, &["tag", "v1"])?;
+
+    git(&work, &["checkout", "--quiet", "-b", "dev"])?;
+    fs::write(work.join("src_file"), "dev")?;
+    git(&work, &["commit", "--quiet", "-am", "dev"])?;
+
+    git(&work, &["checkout", "--quiet", "main"])?;
+    fs::write(work.join("src_file"), "v2")?;
+    git(&work, &["commit", "--quiet", "-am", "v2"])?;
+
+    git(
+        td.path(),
+        &["clone", "--quiet", "--bare", REMOTE_WORK, REMOTE],
+    )?;
+
+    Ok(td.join(REMOTE))
+}
+
+/// Commit a file change to `main` of the remote created by [`git_remote`].
+pub fn git_commit(td: &TempDir, path: &str, content: &str) -> Result<()> {
+    let work = td.join(REMOTE_WORK);
+    fs::write(work.join(path), content)?;
+    git(&work, &["add", "."])?;
+    git(&work, &["commit", "--quiet", "-m", content])?;
+    git(
+        &work,
+        &["push", "--quiet", "--tags", "../remote.git", "main"],
+    )?;
+    Ok(())
+}
@@ -1,4 +1,5 @@
 mod file;
+mod git;
 mod matchers;
 mod package;
 
@@ -18,11 +19,12 @@ pub mod prelude {
 
     pub use super::common_runner;
     pub use super::file::TempDir;
+    pub use super::git::{git_commit, git_remote};
     pub use super::matchers::*;
     pub use super::package::*;
 
-    pub use crate::config::PackageType;
+    pub use crate::config::{LinkStyle, Package, PackageType};
     pub use crate::logger::{LogMessage, NullOutput};
     pub use crate::runner::{LoadError, RunnerError, UnloadError};
-    pub use crate::trace::PkgTrace;
+    pub use crate::trace::{PkgLock, PkgTrace};
 }
@@ -2,7 +2,8 @@ use googletest::Result;
 
 use super::common_runner;
 use super::file::TempDir;
-use crate::config::{NamedPackage, Package, PackageType, VarMap};
+use super::git::git_remote;
+use crate::config::{LinkStyle, NamedPackage, Package, PackageType, VarMap};
 use crate::logger::NullOutput;
 use crate::runner::Runner;
 
@@ -12,6 +13,8 @@ pub const SRC_DIR_PATH: &str = "test_package/src_dir";
 pub const DST_FILE_PATH: &str = "./test_pkg/dst_file";
 pub const DST_DIR_PATH: &str = "./test_a/test_b/dst_dir";
 
+pub const GIT_PKG_DIR: &str = ".pkgs/git/test_package";
+
 pub fn common_local_pkg() -> Result<(TempDir, NamedPackage, Runner<NullOutput>)> {
     let td = TempDir::new()?
         .dir(SRC_DIR_PATH)?
@@ -26,9 +29,38 @@ pub fn common_local_pkg() -> Result<(TempDir, NamedPackage, Runner<NullOutput>)>
             kind: PackageType::Local,
             vars: vec![],
             maps: vec![
-                ("src_file".into(), dst_file_path),
-                ("src_dir".into(), dst_dir_path),
+                ("src_file".into(), dst_file_path.into()),
+                ("src_dir".into(), dst_dir_path.into()),
+            ],
+            ..Default::default()
+        },
+        VarMap::try_new(&[])?,
+    )?;
+
+    let runner = common_runner(td.path());
+
+    Ok((td, pkgs, runner))
+}
+
+/// Create a git package tracking the remote from [`git_remote`], with the same maps as
+/// [`common_local_pkg`]. Revision fields are taken from `revision`.
+pub fn common_git_pkg(revision: Package) -> Result<(TempDir, NamedPackage, Runner<NullOutput>)> {
+    let td = TempDir::new()?;
+    let remote = git_remote(&td)?;
+
+    let dst_file_path = td.join(DST_FILE_PATH).to_string_lossy().into_owned();
+    let dst_dir_path = td.join(DST_DIR_PATH).to_string_lossy().into_owned();
+
+    let pkgs = NamedPackage::try_new(
+        "test_package",
+        Package {
+            kind: PackageType::Git,
+            url: Some(remote.to_string_lossy().into_owned()),
+            maps: vec![
+                ("src_file".into(), dst_file_path.into()),
+                ("src_dir".into(), dst_dir_path.into()),
             ],
+            ..revision
         },
         VarMap::try_new(&[])?,
     )?;
@@ -37,3 +69,39 @@ pub fn common_local_pkg() -> Result<(TempDir, NamedPackage, Runner<NullOutput>)>
 
     Ok((td, pkgs, runner))
 }
+
+/// The local package `pkg` with its symlinks written relative to their parent directory.
+pub fn relative_pkg(pkg: &NamedPackage) -> Result<NamedPackage> {
+    Ok(NamedPackage::try_new(
+        pkg.name(),
+        Package {
+            kind: PackageType::Local,
+            maps: pkg
+                .maps()
+                .iter()
+                .map(|(src, dst)| (src.clone(), dst.as_str().into()))
+                .collect(),
+            link: Some(LinkStyle::Relative),
+            ..Default::default()
+        },
+        VarMap::try_new(&[])?,
+    )?)
+}
+
+/// The git package `pkg` fetched from `url` instead.
+pub fn git_pkg_with_url(pkg: &NamedPackage, url: impl Into<String>) -> Result<NamedPackage> {
+    Ok(NamedPackage::try_new(
+        pkg.name(),
+        Package {
+            kind: PackageType::Git,
+            url: Some(url.into()),
+            maps: pkg
+                .maps()
+                .iter()
+                .map(|(src, dst)| (src.clone(), dst.as_str().into()))
+                .collect(),
+            ..Default::default()
+        },
+        VarMap::try_new(&[])?,
+    )?)
+}
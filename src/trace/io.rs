@@ -6,7 +6,10 @@ use thiserror::Error;
 use toml::de::Error as TomlDeError;
 use toml::ser::Error as TomlSerError;
 
-use super::Trace;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use super::{Lock, Trace};
 
 #[derive(Debug, Error)]
 pub enum TraceIoError {
@@ -18,32 +21,54 @@ pub enum TraceIoError {
 
     #[error(transparent)]
     Serialize(#[from] TomlSerError),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+fn read_from_file<T: DeserializeOwned>(path: &Path) -> Result<T, TraceIoError> {
+    let content = fs::read_to_string(path)?;
+    let value = toml::from_str(&content)?;
+    Ok(value)
+}
+
+fn write_to_file<T: Serialize>(value: &T, path: &Path) -> Result<(), TraceIoError> {
+    let content = toml::to_string(value)?;
+    fs::write(path, content)?;
+    Ok(())
 }
 
 impl Trace {
     pub fn read_from_file(path: &Path) -> Result<Self, TraceIoError> {
-        let content = fs::read_to_string(path)?;
-        let trace = toml::from_str(&content)?;
-        Ok(trace)
+        read_from_file(path)
     }
 
     pub fn write_to_file(&self, path: &Path) -> Result<(), TraceIoError> {
-        let content = toml::to_string(self)?;
-        fs::write(path, content)?;
-        Ok(())
+        write_to_file(self, path)
+    }
+}
+
+impl Lock {
+    pub fn read_from_file(path: &Path) -> Result<Self, TraceIoError> {
+        read_from_file(path)
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> Result<(), TraceIoError> {
+        write_to_file(self, path)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeMap;
+    use std::collections::{BTreeMap, BTreeSet};
     use std::io::Write;
 
     use googletest::prelude::*;
     use tempfile::NamedTempFile;
 
     use super::*;
-    use crate::trace::{PkgTrace, Trace};
+    use crate::config::LinkStyle;
+    use crate::trace::{PkgLock, PkgTrace, Trace};
 
     #[gtest]
     fn write_and_read_trace() {
@@ -53,17 +78,30 @@ mod tests {
                     "pkg1".to_string(),
                     PkgTrace {
                         directory: "dir1".to_string(),
+                        revision: None,
+                        url: None,
                         maps: BTreeMap::from([
                             ("src1".to_string(), "dst1".to_string()),
                             ("src2".to_string(), "dst2".to_string()),
                         ]),
+                        backups: BTreeMap::new(),
+                        dirs: BTreeSet::new(),
+                        link: LinkStyle::Absolute,
                     },
                 ),
                 (
                     "pkg2".to_string(),
                     PkgTrace {
                         directory: "dir2".to_string(),
+                        revision: Some("abc123".to_string()),
+                        url: Some("https://example.com/pkg2.git".to_string()),
                         maps: BTreeMap::from([("src3".to_string(), "dst3".to_string())]),
+                        backups: BTreeMap::from([(
+                            "dst3".to_string(),
+                            "dst3.pkgs-bak".to_string(),
+                        )]),
+                        dirs: BTreeSet::from(["dir3".to_string()]),
+                        link: LinkStyle::Relative,
                     },
                 ),
             ]),
@@ -75,6 +113,24 @@ mod tests {
         assert_eq!(trace, Trace::read_from_file(file.path()).unwrap());
     }
 
+    #[gtest]
+    fn write_and_read_lock() {
+        let lock = Lock {
+            packages: BTreeMap::from([(
+                "pkg1".to_string(),
+                PkgLock {
+                    url: "https://example.com/pkg1.git".to_string(),
+                    revision: "abc123".to_string(),
+                },
+            )]),
+        };
+
+        let file = NamedTempFile::new().unwrap();
+        lock.write_to_file(file.path()).unwrap();
+
+        assert_eq!(lock, Lock::read_from_file(file.path()).unwrap());
+    }
+
     #[gtest]
     fn read_non_existent_file() {
         let result = Trace::read_from_file(Path::new("no_such_file.toml")).unwrap_err();
@@ -0,0 +1,195 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use super::TraceIoError;
+use crate::logger::LogMessage;
+
+/// Write-ahead record of the actions of an operation that has not finished yet.
+///
+/// Every action is appended and synced before it is performed, one json object per line, and
+/// dropped again if it fails.
+pub struct Journal {
+    file: File,
+    /// Offset of the last appended entry
+    last: Option<u64>,
+}
+
+/// An action read back from the journal, with the offset its line starts at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub offset: u64,
+    pub action: LogMessage,
+}
+
+impl Journal {
+    /// Start a new journal, failing if one already exists.
+    pub fn create(path: &Path) -> Result<Self, TraceIoError> {
+        let file = OpenOptions::new()
+            .append(true)
+            .create_new(true)
+            .open(path)?;
+        Ok(Self { file, last: None })
+    }
+
+    pub fn open(path: &Path) -> Result<Self, TraceIoError> {
+        let file = OpenOptions::new().append(true).open(path)?;
+        Ok(Self { file, last: None })
+    }
+
+    pub fn append(&mut self, action: &LogMessage) -> io::Result<()> {
+        let mut line = serde_json::to_string(action).map_err(io::Error::other)?;
+        line.push('\n');
+        let offset = self.file.metadata()?.len();
+        self.file.write_all(line.as_bytes())?;
+        self.file.sync_data()?;
+        self.last = Some(offset);
+        Ok(())
+    }
+
+    /// Drop the last appended entry, whose action failed and has nothing to undo.
+    pub fn discard_last(&mut self) -> io::Result<()> {
+        match self.last.take() {
+            Some(offset) => self.truncate(offset),
+            None => Ok(()),
+        }
+    }
+
+    /// Drop every entry from `offset` on, once it has been undone.
+    pub fn truncate(&mut self, offset: u64) -> io::Result<()> {
+        self.file.set_len(offset)?;
+        self.file.sync_data()
+    }
+
+    /// Read all entries. A last line without newline was cut off by the interruption before its
+    /// action could be performed, so it is ignored.
+    pub fn read(path: &Path) -> Result<Vec<JournalEntry>, TraceIoError> {
+        let content = fs::read_to_string(path)?;
+
+        let mut entries = vec![];
+        let mut offset = 0;
+        for line in content.split_inclusive('\n') {
+            let Some(json) = line.strip_suffix('\n') else {
+                break;
+            };
+            entries.push(JournalEntry {
+                offset,
+                action: serde_json::from_str(json)?,
+            });
+            offset += line.len() as u64;
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use googletest::prelude::*;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn actions() -> [LogMessage; 2] {
+        [
+            LogMessage::CreateDir(PathBuf::from("dir")),
+            LogMessage::CreateSymlink {
+                src: PathBuf::from("src"),
+                dst: PathBuf::from("dir/dst"),
+            },
+        ]
+    }
+
+    #[gtest]
+    fn append_and_read() -> Result<()> {
+        let td = TempDir::new()?;
+        let path = td.path().join("journal");
+
+        let mut journal = Journal::create(&path)?;
+        for action in actions() {
+            journal.append(&action)?;
+        }
+
+        let entries = Journal::read(&path)?;
+        expect_eq!(
+            entries.iter().map(|e| e.action.clone()).collect::<Vec<_>>(),
+            actions()
+        );
+        expect_eq!(entries[0].offset, 0);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn create_twice() -> Result<()> {
+        let td = TempDir::new()?;
+        let path = td.path().join("journal");
+
+        let _journal = Journal::create(&path)?;
+        expect_pred!(Journal::create(&path).is_err());
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn ignore_cut_off_line() -> Result<()> {
+        let td = TempDir::new()?;
+        let path = td.path().join("journal");
+
+        let mut journal = Journal::create(&path)?;
+        journal.append(&actions()[0])?;
+        journal.file.write_all(b"{\"CreateSym")?;
+
+        expect_eq!(Journal::read(&path)?.len(), 1);
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn truncate() -> Result<()> {
+        let td = TempDir::new()?;
+        let path = td.path().join("journal");
+
+        let mut journal = Journal::create(&path)?;
+        for action in actions() {
+            journal.append(&action)?;
+        }
+
+        let entries = Journal::read(&path)?;
+        Journal::open(&path)?.truncate(entries[1].offset)?;
+
+        expect_eq!(
+            Journal::read(&path)?
+                .into_iter()
+                .map(|e| e.action)
+                .collect::<Vec<_>>(),
+            [actions()[0].clone()]
+        );
+
+        Ok(())
+    }
+
+    #[gtest]
+    fn discard_last() -> Result<()> {
+        let td = TempDir::new()?;
+        let path = td.path().join("journal");
+
+        let mut journal = Journal::create(&path)?;
+        for action in actions() {
+            journal.append(&action)?;
+        }
+        journal.discard_last()?;
+        journal.discard_last()?;
+
+        expect_eq!(
+            Journal::read(&path)?
+                .into_iter()
+                .map(|e| e.action)
+                .collect::<Vec<_>>(),
+            [actions()[0].clone()]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Resolved revisions of non-local packages, stored next to the configuration file.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+pub struct Lock {
+    pub packages: BTreeMap<String, PkgLock>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct PkgLock {
+    pub url: String,
+    pub revision: String,
+}
@@ -1,7 +1,11 @@
 mod io;
+mod journal;
+mod lock;
 
 #[allow(clippy::module_inception)]
 mod trace;
 
 pub use io::TraceIoError;
+pub use journal::{Journal, JournalEntry};
+pub use lock::{Lock, PkgLock};
 pub use trace::{PkgTrace, Trace};
@@ -1,7 +1,9 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use serde::{Deserialize, Serialize};
 
+use crate::config::LinkStyle;
+
 #[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
 pub struct Trace {
     pub packages: BTreeMap<String, PkgTrace>,
@@ -11,15 +13,39 @@ pub struct Trace {
 pub struct PkgTrace {
     pub directory: String,
 
+    /// Commit checked out when the package was loaded, only for git packages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+
+    /// Remote the revision was checked out from, only for git packages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
     #[serde(with = "trace_map_as_map")]
     pub maps: BTreeMap<String, String>,
+
+    /// Original files moved away to make room for symlinks, from dst to backup path
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub backups: BTreeMap<String, String>,
+
+    /// Parent directories created for the symlinks, removed on unload once empty
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub dirs: BTreeSet<String>,
+
+    #[serde(default, skip_serializing_if = "LinkStyle::is_absolute")]
+    pub link: LinkStyle,
 }
 
 impl PkgTrace {
     pub fn new(directory: String) -> Self {
         Self {
             directory,
+            revision: None,
+            url: None,
             maps: BTreeMap::new(),
+            backups: BTreeMap::new(),
+            dirs: BTreeSet::new(),
+            link: LinkStyle::default(),
         }
     }
 }
@@ -68,10 +94,15 @@ mod tests {
     fn pkg_trace_ser_de() {
         let trace = PkgTrace {
             directory: "test_dir".to_string(),
+            revision: None,
+            url: None,
             maps: BTreeMap::from([
                 ("src1".to_string(), "dst1".to_string()),
                 ("src2".to_string(), "dst2".to_string()),
             ]),
+            backups: BTreeMap::new(),
+            dirs: BTreeSet::new(),
+            link: LinkStyle::Absolute,
         };
 
         assert_tokens(
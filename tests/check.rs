@@ -0,0 +1,93 @@
+pub mod common;
+
+use std::fs;
+
+use googletest::prelude::*;
+use rstest::*;
+use serde_json::{Value, json};
+use tempfile::TempDir;
+
+use common::{pkgs, stdout};
+
+/// Package `a` with an unknown variable, `b` without its directory.
+#[fixture]
+fn broken() -> TempDir {
+    let td = TempDir::new().unwrap();
+    fs::create_dir(td.path().join("a")).unwrap();
+    fs::write(td.path().join("a/file"), "a").unwrap();
+    fs::write(
+        td.path().join("pkgs.toml"),
+        r#"
+            [packages.a.maps]
+            file = "${UNKNOWN}/file"
+
+            [packages.b.maps]
+            file = "/dst/b"
+        "#,
+    )
+    .unwrap();
+    td
+}
+
+#[rstest]
+#[gtest]
+fn report_problems(broken: TempDir) -> Result<()> {
+    let output = pkgs(&broken, &["check"]).assert().failure();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout).into_owned();
+    expect_that!(stdout, contains_substring("a: unknown variable 'UNKNOWN'"));
+    expect_that!(
+        stdout,
+        contains_substring("b: package directory for 'b' not found")
+    );
+
+    expect_false!(broken.path().join(".pkgs").exists());
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn report_json(broken: TempDir) -> Result<()> {
+    let output = pkgs(&broken, &["validate", "--json"]).assert().failure();
+    let problems: Value = serde_json::from_slice(&output.get_output().stdout)?;
+
+    let kinds: Vec<_> = problems
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| (p["kind"].clone(), p["package"].clone()))
+        .collect();
+    expect_eq!(
+        kinds,
+        [
+            (json!("invalid-var"), json!("a")),
+            (json!("pkg-dir-not-found"), json!("b")),
+        ]
+    );
+    Ok(())
+}
+
+#[gtest]
+fn invalid_config() -> Result<()> {
+    let td = TempDir::new()?;
+    fs::write(td.path().join("pkgs.toml"), "invalid")?;
+
+    let output = pkgs(&td, &["check", "--json"]).assert().failure();
+    let problems: Value = serde_json::from_slice(&output.get_output().stdout)?;
+    expect_eq!(problems[0]["kind"], json!("invalid-config"));
+    Ok(())
+}
+
+#[gtest]
+fn no_problems() -> Result<()> {
+    let td = TempDir::new()?;
+    fs::create_dir(td.path().join("a"))?;
+    fs::write(td.path().join("a/file"), "a")?;
+    fs::write(
+        td.path().join("pkgs.toml"),
+        "[packages.a.maps]\nfile = \"/dst/a\"\n",
+    )?;
+
+    let out = stdout(&mut pkgs(&td, &["check"]));
+    expect_eq!(out, "No problems found.\n");
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use std::fs;
+use std::path::Path;
+
+use assert_cmd::{Command, cargo::cargo_bin};
+use rstest::*;
+use tempfile::TempDir;
+
+/// `pkgs` run in `dir` with `args`.
+pub fn pkgs(dir: impl AsRef<Path>, args: &[&str]) -> Command {
+    let mut cmd = Command::new(cargo_bin!("pkgs"));
+    cmd.current_dir(dir).args(args);
+    cmd
+}
+
+/// Stdout of `cmd`, which must succeed.
+pub fn stdout(cmd: &mut Command) -> String {
+    let output = cmd.assert().success();
+    String::from_utf8_lossy(&output.get_output().stdout).into_owned()
+}
+
+/// Package directories `a` and `b`, each with a `file` holding the package name.
+#[fixture]
+pub fn packages() -> TempDir {
+    let td = TempDir::new().unwrap();
+    for name in ["a", "b"] {
+        fs::create_dir(td.path().join(name)).unwrap();
+        fs::write(td.path().join(name).join("file"), name).unwrap();
+    }
+    td
+}
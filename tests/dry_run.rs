@@ -0,0 +1,48 @@
+pub mod common;
+
+use std::fs;
+
+use googletest::prelude::*;
+use rstest::*;
+use tempfile::TempDir;
+
+use common::{packages, pkgs, stdout};
+
+/// Packages `a` and `b` mapped into `dst`.
+#[fixture]
+fn configured(#[from(packages)] td: TempDir) -> TempDir {
+    fs::write(
+        td.path().join("pkgs.toml"),
+        format!(
+            "[packages.a.maps]\nfile = \"{0}/dst/a\"\n\n[packages.b.maps]\nfile = \"{0}/dst/b\"\n",
+            td.path().display()
+        ),
+    )
+    .unwrap();
+    td
+}
+
+#[rstest]
+#[gtest]
+fn load(configured: TempDir) -> Result<()> {
+    let out = stdout(&mut pkgs(&configured, &["load", "--all", "--dry-run"]));
+
+    expect_that!(out, contains_substring("Would load package: a\n"));
+    expect_that!(out, not(contains_substring("Loaded")));
+    expect_that!(out, ends_with("Summary (dry run):\n  Would load: a, b\n"));
+    expect_false!(configured.path().join("dst/a").exists());
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn unload(configured: TempDir) -> Result<()> {
+    pkgs(&configured, &["load", "--all"]).assert().success();
+
+    let out = stdout(&mut pkgs(&configured, &["unload", "a", "--dry-run"]));
+
+    expect_that!(out, contains_substring("Would unload package: a\n"));
+    expect_that!(out, ends_with("Summary (dry run):\n  Would unload: a\n"));
+    expect_true!(configured.path().join("dst/a").is_symlink());
+    Ok(())
+}
@@ -0,0 +1,135 @@
+pub mod common;
+
+use std::fs;
+
+use googletest::prelude::*;
+use rstest::*;
+use tempfile::TempDir;
+
+use common::{pkgs, stdout};
+
+/// Packages `a`, `b` and `c`, where `b` maps a file that does not exist.
+#[fixture]
+fn broken() -> TempDir {
+    let td = TempDir::new().unwrap();
+    let mut config = String::new();
+    for name in ["a", "b", "c"] {
+        fs::create_dir(td.path().join(name)).unwrap();
+        if name != "b" {
+            fs::write(td.path().join(name).join("file"), name).unwrap();
+        }
+        config.push_str(&format!(
+            "[packages.{name}.maps]\nfile = \"{}/dst/{name}\"\n\n",
+            td.path().display()
+        ));
+    }
+    fs::write(td.path().join("pkgs.toml"), config).unwrap();
+    td
+}
+
+#[rstest]
+#[gtest]
+fn keep_other_packages_by_default(broken: TempDir) -> Result<()> {
+    let output = pkgs(&broken, &["load", "--all"]).assert().code(2);
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout).into_owned();
+    expect_that!(stdout, contains_substring("Loaded: a, c\n  Failed: b\n"));
+
+    expect_true!(broken.path().join("dst/a").is_symlink());
+    expect_true!(broken.path().join("dst/c").is_symlink());
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn fail_fast(broken: TempDir) -> Result<()> {
+    pkgs(&broken, &["load", "--all", "--fail-fast"])
+        .assert()
+        .code(2);
+
+    expect_true!(broken.path().join("dst/a").is_symlink());
+    expect_false!(broken.path().join("dst/c").exists());
+
+    let trace = fs::read_to_string(broken.path().join(".pkgs/trace.toml"))?;
+    expect_that!(trace, contains_substring("packages.a"));
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn success(broken: TempDir) -> Result<()> {
+    let out = stdout(&mut pkgs(&broken, &["load", "a", "c"]));
+    expect_that!(out, ends_with("Summary:\n  Loaded: a, c\n"));
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn roll_back_everything(broken: TempDir) -> Result<()> {
+    let output = pkgs(&broken, &["load", "--all", "--atomic"])
+        .assert()
+        .code(2);
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout).into_owned();
+    expect_that!(stdout, contains_substring("Failed: b\n  Rolled back: a\n"));
+
+    expect_false!(broken.path().join("dst/a").exists());
+    expect_false!(broken.path().join("dst").exists());
+    expect_false!(broken.path().join(".pkgs/trace.toml").exists());
+    expect_false!(broken.path().join(".pkgs/journal.jsonl").exists());
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn keep_trace_unchanged(broken: TempDir) -> Result<()> {
+    pkgs(&broken, &["load", "a"]).assert().success();
+    let trace = fs::read_to_string(broken.path().join(".pkgs/trace.toml"))?;
+
+    pkgs(&broken, &["load", "--all", "--atomic"])
+        .assert()
+        .code(2);
+
+    expect_true!(broken.path().join("dst/a").is_symlink());
+    expect_false!(broken.path().join("dst/c").exists());
+    expect_eq!(
+        fs::read_to_string(broken.path().join(".pkgs/trace.toml"))?,
+        trace
+    );
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn conflicting_destinations(broken: TempDir) -> Result<()> {
+    let config = fs::read_to_string(broken.path().join("pkgs.toml"))?;
+    fs::write(
+        broken.path().join("pkgs.toml"),
+        config.replace("dst/c", "dst/a"),
+    )?;
+
+    let output = pkgs(&broken, &["load", "a"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr).into_owned();
+    expect_that!(stderr, contains_substring("is mapped by both 'a' and 'c'"));
+
+    expect_false!(broken.path().join("dst/a").exists());
+    expect_false!(broken.path().join(".pkgs").exists());
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn unrequested_package_does_not_resolve(broken: TempDir) -> Result<()> {
+    let config = fs::read_to_string(broken.path().join("pkgs.toml"))?;
+    let dst_b = format!("{}/dst/b", broken.path().display());
+    fs::write(
+        broken.path().join("pkgs.toml"),
+        config.replace(&dst_b, "${NOPE}/b"),
+    )?;
+
+    pkgs(&broken, &["load", "a"]).assert().success();
+    expect_true!(broken.path().join("dst/a").is_symlink());
+
+    let output = pkgs(&broken, &["load", "b"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr).into_owned();
+    expect_that!(stderr, contains_substring("unknown variable 'NOPE'"));
+    Ok(())
+}
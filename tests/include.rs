@@ -0,0 +1,111 @@
+pub mod common;
+
+use std::fs;
+
+use googletest::prelude::*;
+use rstest::*;
+use tempfile::TempDir;
+
+use common::{packages, pkgs};
+
+/// Package `a` in `pkgs.toml`, and package `b` in `pkgs.d/b.toml` using a var of `pkgs.toml`.
+#[fixture]
+fn included(#[from(packages)] td: TempDir) -> TempDir {
+    fs::write(
+        td.path().join("pkgs.toml"),
+        format!(
+            r#"
+                include = ["pkgs.d/*.toml"]
+
+                [vars]
+                DST = "{}/dst"
+
+                [packages.a.maps]
+                file = "${{DST}}/a"
+            "#,
+            td.path().display()
+        ),
+    )
+    .unwrap();
+    fs::create_dir(td.path().join("pkgs.d")).unwrap();
+    fs::write(
+        td.path().join("pkgs.d/b.toml"),
+        "[packages.b.maps]\nfile = \"${DST}/b\"\n",
+    )
+    .unwrap();
+    td
+}
+
+#[rstest]
+#[gtest]
+fn load(included: TempDir) -> Result<()> {
+    pkgs(&included, &["list"])
+        .assert()
+        .success()
+        .stdout("a b\n");
+    pkgs(&included, &["load", "--all"]).assert().success();
+
+    expect_true!(included.path().join("dst/a").is_symlink());
+    expect_true!(included.path().join("dst/b").is_symlink());
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn add_to_included_file(included: TempDir) -> Result<()> {
+    let new = included.path().join("dst/new");
+    fs::create_dir_all(included.path().join("dst"))?;
+    fs::write(&new, "new")?;
+
+    pkgs(&included, &["add", "b", &new.to_string_lossy()])
+        .assert()
+        .success();
+
+    expect_that!(
+        fs::read_to_string(included.path().join("pkgs.d/b.toml"))?,
+        contains_substring("new = \"${DST}/new\"")
+    );
+    expect_that!(
+        fs::read_to_string(included.path().join("pkgs.toml"))?,
+        not(contains_substring("new"))
+    );
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn duplicate_package(included: TempDir) -> Result<()> {
+    fs::write(included.path().join("pkgs.d/a.toml"), "[packages.a]\n")?;
+
+    let output = pkgs(&included, &["list"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr).into_owned();
+    expect_that!(
+        stderr,
+        contains_substring(format!(
+            "package 'a' in '{}' is already defined in '{}'",
+            included.path().join("pkgs.d/a.toml").display(),
+            included.path().join("pkgs.toml").display()
+        ))
+    );
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn invalid_fragment(included: TempDir) -> Result<()> {
+    fs::write(
+        included.path().join("pkgs.d/c.toml"),
+        "invalid toml content",
+    )?;
+
+    let output = pkgs(&included, &["list"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr).into_owned();
+    expect_that!(
+        stderr,
+        contains_substring(format!(
+            "failed to read included file '{}'",
+            included.path().join("pkgs.d/c.toml").display()
+        ))
+    );
+    Ok(())
+}
@@ -0,0 +1,73 @@
+pub mod common;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command as Process;
+
+use googletest::prelude::*;
+use rstest::*;
+use tempfile::TempDir;
+
+use common::pkgs;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Process::new("git")
+        .args(["-c", "user.name=pkgs", "-c", "user.email=pkgs@example.com"])
+        .args(["-c", "commit.gpgsign=false"])
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {} failed", args.join(" "));
+}
+
+fn write_config(td: &Path, url: &Path) {
+    fs::write(
+        td.join("pkgs.toml"),
+        format!(
+            "[packages.a]\nkind = \"git\"\nurl = \"{}\"\n\n[packages.a.maps]\nfile = \"{}/dst\"\n",
+            url.display(),
+            td.display()
+        ),
+    )
+    .unwrap();
+}
+
+/// Git package `a` cloned from the repository `remote` and loaded.
+#[fixture]
+fn loaded() -> TempDir {
+    let td = TempDir::new().unwrap();
+    let remote = td.path().join("remote");
+    fs::create_dir(&remote).unwrap();
+    git(&remote, &["init", "--quiet", "--initial-branch=main"]);
+    fs::write(remote.join("file"), "a").unwrap();
+    git(&remote, &["add", "."]);
+    git(&remote, &["commit", "--quiet", "-m", "a"]);
+
+    write_config(td.path(), &remote);
+    pkgs(&td, &["load", "--all"]).assert().success();
+    td
+}
+
+#[rstest]
+#[gtest]
+fn url_changed(loaded: TempDir) -> Result<()> {
+    let lock = fs::read_to_string(loaded.path().join("pkgs.lock"))?;
+    expect_that!(
+        lock,
+        contains_substring(format!(
+            "url = \"{}\"",
+            loaded.path().join("remote").display()
+        ))
+    );
+    write_config(loaded.path(), &loaded.path().join("other"));
+
+    for command in ["load", "update"] {
+        let output = pkgs(&loaded, &[command, "--all"]).assert().failure();
+        let stderr = String::from_utf8_lossy(&output.get_output().stderr).into_owned();
+        expect_that!(stderr, contains_substring("remove it to clone again"));
+        expect_eq!(fs::read_to_string(loaded.path().join("pkgs.lock"))?, lock);
+    }
+    Ok(())
+}
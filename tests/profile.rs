@@ -0,0 +1,99 @@
+pub mod common;
+
+use std::fs;
+
+use googletest::prelude::*;
+use rstest::*;
+use tempfile::TempDir;
+
+use common::{packages, pkgs, stdout};
+
+/// Packages `a` and `b`, where the `server` profile disables `b` and moves `a`.
+#[fixture]
+fn profiles(#[from(packages)] td: TempDir) -> TempDir {
+    fs::write(
+        td.path().join("pkgs.toml"),
+        format!(
+            r#"
+                [vars]
+                DST = "{}/dst"
+
+                [packages.a.maps]
+                file = "${{DST}}/a"
+
+                [packages.b.maps]
+                file = "${{DST}}/b"
+
+                [profiles.server.packages.a.maps]
+                file = "${{DST}}/server-a"
+
+                [profiles.server.packages.b]
+                enabled = false
+            "#,
+            td.path().display()
+        ),
+    )
+    .unwrap();
+    td
+}
+
+#[rstest]
+#[gtest]
+fn list(profiles: TempDir) -> Result<()> {
+    expect_eq!(stdout(&mut pkgs(&profiles, &["list"])), "a b\n");
+    expect_eq!(
+        stdout(&mut pkgs(&profiles, &["list", "--profile", "server"])),
+        "Profile: server\na\n"
+    );
+    expect_eq!(
+        stdout(&mut pkgs(&profiles, &["--profile", "server", "list", "-1"])),
+        "a\n"
+    );
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn load(profiles: TempDir) -> Result<()> {
+    pkgs(&profiles, &["load", "--all", "--profile", "server"])
+        .assert()
+        .success();
+
+    expect_true!(profiles.path().join("dst/server-a").is_symlink());
+    expect_false!(profiles.path().join("dst/a").exists());
+    expect_false!(profiles.path().join("dst/b").exists());
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn unknown_profile(profiles: TempDir) -> Result<()> {
+    let output = pkgs(&profiles, &["list", "--profile", "laptop"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr).into_owned();
+    expect_that!(stderr, contains_substring("profile 'laptop' not found"));
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn keep_lock_of_disabled_package(profiles: TempDir) -> Result<()> {
+    let config = fs::read_to_string(profiles.path().join("pkgs.toml"))?;
+    fs::write(
+        profiles.path().join("pkgs.toml"),
+        config.replace(
+            "[packages.b.maps]",
+            "[packages.b]\nkind = \"git\"\nurl = \"https://example.com/b.git\"\n\n[packages.b.maps]",
+        ),
+    )?;
+    let lock = "[packages.b]\nurl = \"https://example.com/b.git\"\nrevision = \"0123abc\"\n";
+    fs::write(profiles.path().join("pkgs.lock"), lock)?;
+
+    pkgs(&profiles, &["load", "--all", "--profile", "server"])
+        .assert()
+        .success();
+
+    expect_eq!(fs::read_to_string(profiles.path().join("pkgs.lock"))?, lock);
+    Ok(())
+}
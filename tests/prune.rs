@@ -0,0 +1,89 @@
+pub mod common;
+
+use std::fs;
+use std::path::Path;
+
+use googletest::prelude::*;
+use rstest::*;
+use tempfile::TempDir;
+
+use common::{packages, pkgs, stdout};
+
+fn config(td: &Path, packages: &[&str]) -> String {
+    packages
+        .iter()
+        .map(|name| {
+            format!(
+                "[packages.{name}.maps]\nfile = \"{}/dst/{name}\"\n",
+                td.display()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Load packages `a` and `b`, then remove `a` from the configuration file.
+#[fixture]
+fn loaded(#[from(packages)] td: TempDir) -> TempDir {
+    fs::create_dir(td.path().join("dst")).unwrap();
+
+    fs::write(td.path().join("pkgs.toml"), config(td.path(), &["a", "b"])).unwrap();
+    pkgs(&td, &["load", "--all"]).assert().success();
+    fs::write(td.path().join("pkgs.toml"), config(td.path(), &["b"])).unwrap();
+
+    td
+}
+
+#[rstest]
+#[gtest]
+fn unload_removed_package(loaded: TempDir) -> Result<()> {
+    pkgs(&loaded, &["unload", "a"]).assert().success();
+
+    expect_false!(loaded.path().join("dst/a").is_symlink());
+    expect_true!(loaded.path().join("dst/b").is_symlink());
+
+    let trace = fs::read_to_string(loaded.path().join(".pkgs/trace.toml"))?;
+    expect_that!(trace, not(contains_substring("packages.a")));
+    expect_that!(trace, contains_substring("packages.b"));
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn unload_all_includes_removed_packages(loaded: TempDir) -> Result<()> {
+    pkgs(&loaded, &["unload", "--all"]).assert().success();
+
+    expect_false!(loaded.path().join("dst/a").is_symlink());
+    expect_false!(loaded.path().join("dst/b").is_symlink());
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn prune(loaded: TempDir) -> Result<()> {
+    pkgs(&loaded, &["prune", "--dry-run"]).assert().success();
+    expect_true!(loaded.path().join("dst/a").is_symlink());
+
+    pkgs(&loaded, &["prune"]).assert().success();
+    expect_false!(loaded.path().join("dst/a").is_symlink());
+    expect_true!(loaded.path().join("dst/b").is_symlink());
+
+    let out = stdout(&mut pkgs(&loaded, &["prune"]));
+    expect_that!(out, contains_substring("Nothing to prune."));
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn prune_with_missing_dst(loaded: TempDir) -> Result<()> {
+    fs::remove_file(loaded.path().join("dst/a"))?;
+
+    pkgs(&loaded, &["prune", "--strict"]).assert().failure();
+
+    let out = stdout(&mut pkgs(&loaded, &["prune"]));
+    expect_that!(out, contains_substring("Warning! Skip Missing Symlink"));
+
+    let trace = fs::read_to_string(loaded.path().join(".pkgs/trace.toml"))?;
+    expect_that!(trace, not(contains_substring("packages.a")));
+    Ok(())
+}
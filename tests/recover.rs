@@ -0,0 +1,73 @@
+pub mod common;
+
+use std::fs;
+use std::path::Path;
+
+use googletest::prelude::*;
+use rstest::*;
+use tempfile::TempDir;
+
+use common::{pkgs, stdout};
+
+fn journal_line(action: &str, src: &Path, dst: &Path) -> String {
+    format!(
+        "{{\"{action}\":{{\"src\":\"{}\",\"dst\":\"{}\"}}}}\n",
+        src.display(),
+        dst.display()
+    )
+}
+
+/// A load of package `a` killed after creating its symlink but before writing the trace.
+#[fixture]
+fn interrupted() -> TempDir {
+    let td = TempDir::new().unwrap();
+    let src = td.path().join("a/file");
+    let dst = td.path().join("dst");
+
+    fs::create_dir(td.path().join("a")).unwrap();
+    fs::write(&src, "a").unwrap();
+    fs::write(
+        td.path().join("pkgs.toml"),
+        format!("[packages.a.maps]\nfile = \"{}\"\n", dst.display()),
+    )
+    .unwrap();
+
+    fs::create_dir(td.path().join(".pkgs")).unwrap();
+    std::os::unix::fs::symlink(&src, &dst).unwrap();
+    fs::write(
+        td.path().join(".pkgs/journal.jsonl"),
+        journal_line("CreateSymlink", &src, &dst),
+    )
+    .unwrap();
+
+    td
+}
+
+#[rstest]
+#[gtest]
+fn refuse_to_run_before_recover(interrupted: TempDir) -> Result<()> {
+    let output = pkgs(&interrupted, &["load", "--all"]).assert().failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr).into_owned();
+    expect_that!(stderr, contains_substring("pkgs recover"));
+
+    pkgs(&interrupted, &["status"]).assert().failure();
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn recover(interrupted: TempDir) -> Result<()> {
+    let out = stdout(&mut pkgs(&interrupted, &["recover"]));
+    expect_that!(out, contains_substring("Recovered interrupted operation."));
+
+    expect_false!(interrupted.path().join("dst").is_symlink());
+    expect_false!(interrupted.path().join(".pkgs/journal.jsonl").exists());
+
+    pkgs(&interrupted, &["load", "--all"]).assert().success();
+    expect_true!(interrupted.path().join("dst").is_symlink());
+    expect_false!(interrupted.path().join(".pkgs/journal.jsonl").exists());
+
+    let out = stdout(&mut pkgs(&interrupted, &["recover"]));
+    expect_that!(out, contains_substring("Nothing to recover."));
+    Ok(())
+}
@@ -0,0 +1,52 @@
+pub mod common;
+
+use std::fs;
+
+use googletest::prelude::*;
+use rstest::*;
+use tempfile::TempDir;
+
+use common::{pkgs, stdout};
+
+/// A repository with package `a` loaded, which is then moved to `moved`.
+#[fixture]
+fn moved() -> TempDir {
+    let td = TempDir::new().unwrap();
+    let repo = td.path().join("repo");
+    fs::create_dir_all(repo.join("a")).unwrap();
+    fs::write(repo.join("a/file"), "a").unwrap();
+    fs::write(
+        repo.join("pkgs.toml"),
+        format!(
+            "[packages.a.maps]\nfile = \"{}/dst\"\n",
+            td.path().display()
+        ),
+    )
+    .unwrap();
+
+    pkgs(&repo, &["load", "--all"]).assert().success();
+    fs::rename(&repo, td.path().join("moved")).unwrap();
+
+    td
+}
+
+#[rstest]
+#[gtest]
+fn relink_after_move(moved: TempDir) -> Result<()> {
+    let repo = moved.path().join("moved");
+    let dst = moved.path().join("dst");
+    expect_false!(dst.exists());
+
+    pkgs(&repo, &["relink", "--all", "--dry-run"])
+        .assert()
+        .success();
+    expect_false!(dst.exists());
+
+    let out = stdout(&mut pkgs(&repo, &["relink", "--all"]));
+    expect_that!(out, contains_substring("Relinked package: a"));
+    expect_eq!(fs::read_link(&dst)?, repo.join("a/file").canonicalize()?);
+
+    let out = stdout(&mut pkgs(&repo, &["relink", "--all"]));
+    expect_that!(out, contains_substring("Nothing to relink."));
+    Ok(())
+}
@@ -15,6 +15,8 @@ fn schema() -> JsonValue {
 #[fixture]
 fn content() -> String {
     indoc! {r#"
+        include = ["pkgs.d/*.toml", "other.yaml"]
+
         [vars]
         MY_VAR = "Hello"
         ANOTHER_VAR = "World"
@@ -29,6 +31,11 @@ fn content() -> String {
         src_file = "dst_file"
         "path/to/src_dir" = "path/to/dst_dir"
         "a.with_ext" = "b.with_ext"
+        "config.nu" = { dst = "nu_dst", when = { command = ["nu"], env = ["NU_HOME"] } }
+
+        [packages.a.when]
+        os = ["linux", "macos"]
+        host = ["work-*"]
 
         [packages."empty maps"]
     "#}
@@ -63,6 +70,7 @@ fn kind_can_omit(schema: JsonValue, mut content: String) -> Result<()> {
 #[case("packages.a")]
 #[case("packages.a.vars")]
 #[case("packages.a.maps")]
+#[case("packages.a.when")]
 #[gtest]
 fn some_field_can_omit(schema: JsonValue, mut content: String, #[case] field: &str) -> Result<()> {
     let start = content.find(&format!("[{field}]")).unwrap();
@@ -74,10 +82,35 @@ fn some_field_can_omit(schema: JsonValue, mut content: String, #[case] field: &s
     Ok(())
 }
 
+#[rstest]
+#[case(r#"url = "https://example.com/repo.git""#)]
+#[case(
+    r#"url = "https://example.com/repo.git"
+branch = "main""#
+)]
+#[case(
+    r#"url = "https://example.com/repo.git"
+tag = "v1.0.0""#
+)]
+#[case(
+    r#"url = "https://example.com/repo.git"
+commit = "0123abc""#
+)]
+#[gtest]
+fn git_package(schema: JsonValue, mut content: String, #[case] fields: &str) -> Result<()> {
+    content = content.replace("kind = \"local\"", &format!("kind = \"git\"\n{fields}"));
+
+    let value = read_toml(&content)?;
+    assert_that!(validate(&schema, &value), ok(anything()));
+    Ok(())
+}
+
 #[rstest]
 #[case("kind", "type")]
 #[case("packages.a.vars", "packages.a.var")]
 #[case("packages.a.maps", "packages.a.map")]
+#[case("os = ", "system = ")]
+#[case("dst = ", "path = ")]
 #[gtest]
 fn unknown_fields(
     schema: JsonValue,
@@ -0,0 +1,52 @@
+pub mod common;
+
+use std::fs;
+
+use googletest::prelude::*;
+use tempfile::TempDir;
+
+use common::pkgs;
+
+#[gtest]
+fn builtin_and_env_vars() -> Result<()> {
+    let td = TempDir::new()?;
+    fs::create_dir(td.path().join("a"))?;
+    fs::write(td.path().join("a/file"), "a")?;
+    fs::write(
+        td.path().join("pkgs.toml"),
+        r#"
+            [packages.a.vars]
+            DST = "${env:PKGS_TEST_DST:-${PKG_DIR}/../unused}"
+
+            [packages.a.maps]
+            file = "${DST}/${PKG_NAME}-${OS}"
+        "#,
+    )?;
+
+    let dst = td.path().join("dst");
+    pkgs(&td, &["load", "a"])
+        .env("PKGS_TEST_DST", &dst)
+        .assert()
+        .success();
+
+    let link = dst.join(format!("a-{}", std::env::consts::OS));
+    expect_true!(link.is_symlink());
+    expect_eq!(fs::read_to_string(link)?, "a");
+    Ok(())
+}
+
+#[gtest]
+fn package_dir() -> Result<()> {
+    let td = TempDir::new()?;
+    fs::create_dir(td.path().join("a"))?;
+    fs::write(td.path().join("a/file"), "a")?;
+    fs::write(
+        td.path().join("pkgs.toml"),
+        "[packages.a.maps]\nfile = \"${PKG_DIR}/../dst/file\"\n",
+    )?;
+
+    pkgs(&td, &["load", "a"]).assert().success();
+
+    expect_true!(td.path().join("dst/file").is_symlink());
+    Ok(())
+}
@@ -0,0 +1,83 @@
+pub mod common;
+
+use std::fs;
+
+use googletest::prelude::*;
+use rstest::*;
+use tempfile::TempDir;
+
+use common::{packages, pkgs, stdout};
+
+/// Package `a` with a map only used if an unknown command exists, and package `b` only used on
+/// an unknown OS.
+#[fixture]
+fn conditional(#[from(packages)] td: TempDir) -> TempDir {
+    fs::write(td.path().join("a/extra"), "extra").unwrap();
+    fs::write(
+        td.path().join("pkgs.toml"),
+        format!(
+            r#"
+                [vars]
+                DST = "{}/dst"
+
+                [packages.a.maps]
+                file = "${{DST}}/a"
+                extra = {{ dst = "${{DST}}/extra", when = {{ command = ["pkgs-missing-command"] }} }}
+
+                [packages.b]
+                when = {{ os = ["none"] }}
+
+                [packages.b.maps]
+                file = "${{UNKNOWN}}/b"
+            "#,
+            td.path().display()
+        ),
+    )
+    .unwrap();
+    td
+}
+
+#[rstest]
+#[gtest]
+fn list(conditional: TempDir) -> Result<()> {
+    expect_eq!(
+        stdout(&mut pkgs(&conditional, &["list"])),
+        "a\nSkipped: b\n"
+    );
+    expect_eq!(stdout(&mut pkgs(&conditional, &["list", "-1"])), "a\nb\n");
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn load(conditional: TempDir) -> Result<()> {
+    let out = stdout(&mut pkgs(&conditional, &["load", "--all"]));
+    expect_that!(out, contains_substring("Skipped package: b"));
+
+    expect_true!(conditional.path().join("dst/a").is_symlink());
+    expect_false!(conditional.path().join("dst/extra").exists());
+    expect_false!(conditional.path().join("dst/b").exists());
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn status(conditional: TempDir) -> Result<()> {
+    pkgs(&conditional, &["load", "--all"]).assert().success();
+
+    expect_eq!(
+        stdout(&mut pkgs(&conditional, &["status"])),
+        "a: up to date\n  'extra' skipped\nb: skipped\n"
+    );
+    Ok(())
+}
+
+#[rstest]
+#[gtest]
+fn check(conditional: TempDir) -> Result<()> {
+    pkgs(&conditional, &["check"])
+        .assert()
+        .success()
+        .stdout("No problems found.\n");
+    Ok(())
+}